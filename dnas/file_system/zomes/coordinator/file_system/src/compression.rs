@@ -0,0 +1,78 @@
+//! This module provides the compression of file chunks with the codec recorded in the file metadata.
+
+use file_system_integrity::*;
+use hdk::prelude::*;
+
+/// Compression level of the DEFLATE codec, the default level balancing speed and ratio.
+const DEFLATE_LEVEL: u8 = 6;
+
+/// Compresses the content of a chunk with the given codec.
+pub fn compress_chunk(codec: CompressionCodec, content: &[u8]) -> Vec<u8> {
+    match codec {
+        CompressionCodec::Deflate => miniz_oxide::deflate::compress_to_vec(content, DEFLATE_LEVEL),
+    }
+}
+
+/// Decompresses the content of a chunk with the given codec, failing if it is larger than the maximum chunk size.
+pub fn decompress_chunk(
+    codec: CompressionCodec,
+    bytes: &[u8],
+    max_chunk_size: usize,
+) -> ExternResult<Vec<u8>> {
+    match codec {
+        CompressionCodec::Deflate => {
+            miniz_oxide::inflate::decompress_to_vec_with_limit(bytes, max_chunk_size).map_err(|e| {
+                wasm_error!(WasmErrorInner::Guest(format!(
+                    "Could not decompress the file chunk: {:?}",
+                    e.status
+                )))
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_chunk() {
+        let cases = vec![
+            Vec::new(),
+            b"hello world !".to_vec(),
+            "log line\n".repeat(1000).into_bytes(),
+            (0..=255).cycle().take(70_000).collect::<Vec<u8>>(),
+            pseudo_random_bytes(140_000),
+        ];
+
+        for content in cases {
+            let compressed = compress_chunk(CompressionCodec::Deflate, &content);
+            assert!(
+                compressed.len()
+                    <= content.len() + CompressionCodec::Deflate.max_overhead(content.len()),
+                "Failed for input: {:?}",
+                content.len()
+            );
+            assert_eq!(
+                decompress_chunk(CompressionCodec::Deflate, &compressed, content.len()).unwrap(),
+                content,
+                "Failed for input: {:?}",
+                content.len()
+            );
+        }
+
+        let compressed = compress_chunk(CompressionCodec::Deflate, &"a".repeat(1000).into_bytes());
+        assert!(compressed.len() < 100);
+        assert!(decompress_chunk(CompressionCodec::Deflate, &compressed, 999).is_err());
+    }
+
+    fn pseudo_random_bytes(length: usize) -> Vec<u8> {
+        let mut state: u32 = 42;
+        (0..length)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (state >> 16) as u8
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,268 @@
+//! This module provides functions to handle directories. It includes operations such as
+//! creating, deleting and listing directories, built on top of the file system path tree.
+
+use crate::delete_file;
+use crate::files::*;
+use crate::homes::resolve_fs_path;
+use crate::private_files::*;
+use file_system_integrity::*;
+use hdk::prelude::*;
+
+/// A struct representing the input for creating a new directory in the File Storage zome.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct DirectoryInput {
+    pub name: String,
+    pub path: String,
+}
+
+/// A struct representing the input for deleting a directory in the File Storage zome.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct DeleteDirectoryInput {
+    pub path: String,
+    pub recursive: bool,
+}
+
+/// A struct representing a child folder of a listed directory.
+/// Folders created implicitly by `create_file` have no directory metadata.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct DirectoryOutput {
+    pub name: String,
+    pub path: String,
+    pub directory_metadata: Option<Record>,
+}
+
+/// A struct representing the immediate content of a directory.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct DirectoryContent {
+    pub files: Vec<Record>,
+    pub directories: Vec<DirectoryOutput>,
+}
+
+/// Creates a new directory with its metadata at the specified path.
+#[hdk_extern]
+pub fn create_directory(directory_input: DirectoryInput) -> ExternResult<Record> {
+    let parent_path = resolve_fs_path(&directory_input.path)?;
+    let name = normalize_name(&directory_input.name);
+    let directory_path = join_fs_path(&parent_path, &name);
+
+    if get_directory_metadata_by_path(directory_path.clone())?.is_some() {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Directory already exists"
+        ))));
+    }
+
+    if get_file_metadata_by_path_and_name(parent_path.clone(), name.clone()).is_ok() {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "A file with the same name already exists"
+        ))));
+    }
+
+    let now = sys_time()?;
+    let directory_metadata = DirectoryMetadata {
+        name,
+        author: agent_info()?.agent_initial_pubkey,
+        path: parent_path,
+        created: now,
+        last_modified: now,
+    };
+
+    let action_hash = create_entry(&EntryTypes::DirectoryMetadata(directory_metadata))?;
+
+    let typed_path = fs_path_to_dht_path(&directory_path, &file_system_properties()?)
+        .typed(LinkTypes::PathFileSystem)?;
+    typed_path.ensure()?;
+
+    create_link(
+        typed_path.path_entry_hash()?,
+        action_hash.clone(),
+        LinkTypes::PathToDirectoryMetaData,
+        (),
+    )?;
+
+    let record =
+        get(action_hash, GetOptions::default())?.ok_or(wasm_error!(WasmErrorInner::Guest(
+            String::from("Could not find the newly created directory metadata")
+        )))?;
+
+    Ok(record)
+}
+
+/// Lists the files and folders directly within the specified directory path, including the private files of the
+/// calling agent and the folders holding them.
+#[hdk_extern]
+pub fn list_directory(path_string: String) -> ExternResult<DirectoryContent> {
+    let directory_path = resolve_fs_path(&path_string)?;
+    let properties = file_system_properties()?;
+    let typed_path =
+        fs_path_to_dht_path(&directory_path, &properties).typed(LinkTypes::PathFileSystem)?;
+
+    let private_files = get_private_files_metadata_by_path(&directory_path, false)?;
+    let private_directories_names = get_private_directories_names(&directory_path)?;
+
+    let mut files = Vec::new();
+    let mut directories = Vec::new();
+    if typed_path.exists()? {
+        files = get_files_metadata(typed_path.path.clone())?;
+        directories = get_child_directories(&typed_path)?;
+    } else if directory_path != "/"
+        && private_files.is_empty()
+        && private_directories_names.is_empty()
+    {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Directory not found"
+        ))));
+    }
+
+    files.extend(private_files);
+    for name in private_directories_names {
+        if directories
+            .iter()
+            .any(|directory| properties.name_key(&directory.name) == properties.name_key(&name))
+        {
+            continue;
+        }
+        directories.push(DirectoryOutput {
+            path: join_fs_path(&directory_path, &name),
+            name,
+            directory_metadata: None,
+        });
+    }
+
+    Ok(DirectoryContent { files, directories })
+}
+
+/// Lists the folders of the file system tree directly within a directory path, with their metadata if any.
+/// The name and path of a folder are taken from its metadata when it has one, since the components of the
+/// Holochain paths are lowercased in case-insensitive mode.
+fn get_child_directories(typed_path: &TypedPath) -> ExternResult<Vec<DirectoryOutput>> {
+    let mut directories = Vec::new();
+    for child_path in typed_path.children_paths()? {
+        let name = match child_path.leaf() {
+            Some(component) => String::try_from(component).map_err(|e| wasm_error!(e))?,
+            None => continue,
+        };
+        let path = dht_path_to_fs_path(&child_path.path)?;
+        let directory_metadata = get_directory_metadata_by_path(path.clone())?;
+
+        let (name, path) = match &directory_metadata {
+            Some(record) => {
+                let metadata = DirectoryMetadata::try_from(record.clone())?;
+                let path = join_fs_path(&metadata.path, &metadata.name);
+                (metadata.name, path)
+            }
+            None => (name, path),
+        };
+
+        directories.push(DirectoryOutput {
+            name,
+            path,
+            directory_metadata,
+        });
+    }
+
+    Ok(directories)
+}
+
+/// Deletes a directory. Non-recursive deletion fails if the directory still contains files or folders,
+/// recursive deletion deletes every file and folder below it.
+#[hdk_extern]
+pub fn delete_directory(
+    delete_directory_input: DeleteDirectoryInput,
+) -> ExternResult<Vec<ActionHash>> {
+    let directory_path = resolve_fs_path(&delete_directory_input.path)?;
+
+    if directory_path == "/" {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Cannot delete the root directory"
+        ))));
+    }
+
+    let content = list_directory(directory_path.clone())?;
+    if !delete_directory_input.recursive
+        && (!content.files.is_empty() || !content.directories.is_empty())
+    {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Directory is not empty"
+        ))));
+    }
+
+    let mut delete_actions = Vec::new();
+    for file_metadata_record in get_private_files_metadata_by_path(&directory_path, true)? {
+        delete_actions.extend(delete_file(file_metadata_record.action_address().clone())?);
+    }
+    delete_actions.extend(delete_directory_recursively(directory_path)?);
+
+    Ok(delete_actions)
+}
+
+/// Retrieves the directory metadata linked to the specified directory path, if any.
+pub fn get_directory_metadata_by_path(path: String) -> ExternResult<Option<Record>> {
+    let typed_path =
+        fs_path_to_dht_path(&path, &file_system_properties()?).typed(LinkTypes::PathFileSystem)?;
+    let directory_links = get_links(
+        typed_path.path_entry_hash()?,
+        LinkTypes::PathToDirectoryMetaData,
+        None,
+    )?;
+
+    for link in directory_links {
+        if let Some(record) = get(ActionHash::from(link.target), GetOptions::default())? {
+            return Ok(Some(record));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Deletes the files, subfolders and metadata of a directory, then detaches it from its parent.
+fn delete_directory_recursively(path: String) -> ExternResult<Vec<ActionHash>> {
+    let mut delete_actions: Vec<ActionHash> = Vec::new();
+    let typed_path =
+        fs_path_to_dht_path(&path, &file_system_properties()?).typed(LinkTypes::PathFileSystem)?;
+
+    let files_links = get_links(
+        typed_path.path_entry_hash()?,
+        LinkTypes::PathToFileMetaData,
+        None,
+    )?;
+    for link in files_links {
+        let original_file_metadata_hash = ActionHash::from(link.target);
+        if get_latest_file_metadata(original_file_metadata_hash.clone())?.is_some() {
+            delete_actions.extend(delete_file(original_file_metadata_hash)?);
+        }
+        delete_actions.push(delete_link(link.create_link_hash)?);
+    }
+
+    for child_path in typed_path.children_paths()? {
+        if let Some(component) = child_path.leaf() {
+            let name = String::try_from(component).map_err(|e| wasm_error!(e))?;
+            delete_actions.extend(delete_directory_recursively(join_fs_path(&path, &name))?);
+        }
+    }
+
+    let directory_links = get_links(
+        typed_path.path_entry_hash()?,
+        LinkTypes::PathToDirectoryMetaData,
+        None,
+    )?;
+    for link in directory_links {
+        let directory_metadata_hash = ActionHash::from(link.target);
+        if get(directory_metadata_hash.clone(), GetOptions::default())?.is_some() {
+            delete_actions.push(delete_entry(directory_metadata_hash)?);
+        }
+        delete_actions.push(delete_link(link.create_link_hash)?);
+    }
+
+    if let Some(parent) = typed_path.parent() {
+        let path_links = get_links(
+            parent.path_entry_hash()?,
+            LinkTypes::PathFileSystem,
+            Some(typed_path.make_tag()?),
+        )?;
+        for link in path_links {
+            delete_actions.push(delete_link(link.create_link_hash)?);
+        }
+    }
+
+    Ok(delete_actions)
+}
@@ -0,0 +1,174 @@
+//! This module provides the end-to-end encryption of file contents. Each agent publishes an X25519 key from the lair
+//! keystore. The chunks of an encrypted file are encrypted with a random symmetric key of the keystore, which is
+//! wrapped for each agent allowed to read the file and stored in its metadata.
+
+use crate::sharing::share_caller;
+use file_system_integrity::*;
+use hdk::prelude::*;
+use holochain_integrity_types::x_salsa20_poly1305::nonce::{XSalsa20Poly1305Nonce, NONCE_BYTES};
+
+/// Creates an X25519 key in the keystore for the calling agent and publishes it, so that files can be encrypted
+/// for them.
+pub fn create_my_encryption_key() -> ExternResult<()> {
+    let encryption_key = EncryptionKey(create_x25519_keypair()?);
+    let action_hash = create_entry(&EntryTypes::EncryptionKey(encryption_key))?;
+
+    create_link(
+        agent_info()?.agent_initial_pubkey,
+        action_hash,
+        LinkTypes::AgentToEncryptionKey,
+        (),
+    )?;
+
+    Ok(())
+}
+
+/// Returns the latest X25519 key published by an agent, if any.
+#[hdk_extern]
+pub fn get_encryption_key(agent: AgentPubKey) -> ExternResult<Option<X25519PubKey>> {
+    let links = get_links(agent, LinkTypes::AgentToEncryptionKey, None)?;
+    let latest_link = match links.into_iter().max_by_key(|link| link.timestamp) {
+        Some(link) => link,
+        None => return Ok(None),
+    };
+
+    let record = get(ActionHash::from(latest_link.target), GetOptions::default())?;
+    match record {
+        Some(record) => Ok(Some(EncryptionKey::try_from(record)?.0)),
+        None => Ok(None),
+    }
+}
+
+/// Creates a random symmetric key for a new file and wraps it for the calling agent and the given agents.
+pub fn create_file_encryption(
+    agents: &[AgentPubKey],
+) -> ExternResult<(XSalsa20Poly1305KeyRef, FileEncryption)> {
+    let my_pub_key = agent_info()?.agent_initial_pubkey;
+    let sender = get_my_encryption_key()?;
+    let key_ref = x_salsa20_poly1305_shared_secret_create_random(None)?;
+
+    let mut recipients = vec![my_pub_key];
+    for agent in agents {
+        if !recipients.contains(agent) {
+            recipients.push(agent.clone());
+        }
+    }
+
+    let mut wrapped_keys = Vec::new();
+    for agent in recipients {
+        let recipient = get_encryption_key(agent.clone())?.ok_or(wasm_error!(
+            WasmErrorInner::Guest(String::from("Agent has not published an encryption key"))
+        ))?;
+        let wrapped_key =
+            x_salsa20_poly1305_shared_secret_export(sender, recipient, key_ref.clone())?;
+
+        wrapped_keys.push(WrappedFileKey {
+            agent,
+            recipient,
+            wrapped_key,
+        });
+    }
+
+    Ok((
+        key_ref,
+        FileEncryption {
+            sender,
+            wrapped_keys,
+        },
+    ))
+}
+
+/// Returns whether the chunks of a file can be decoded on this call, that is if the file is not encrypted, or if
+/// it is encrypted for the agent of this cell and the call is made by that agent.
+pub fn can_open_file_key(file_metadata: &FileMetadata) -> ExternResult<bool> {
+    let encryption = match &file_metadata.encryption {
+        Some(encryption) => encryption,
+        None => return Ok(true),
+    };
+    if share_caller()?.is_some() {
+        return Ok(false);
+    }
+
+    let my_pub_key = agent_info()?.agent_initial_pubkey;
+    Ok(encryption
+        .wrapped_keys
+        .iter()
+        .any(|wrapped_key| wrapped_key.agent == my_pub_key))
+}
+
+/// Unwraps the symmetric key of a file into the keystore of the agent of this cell, if the file is encrypted.
+///
+/// The key can only be unwrapped on calls made by the agent itself, other agents calling through a share grant
+/// have to decrypt the file in their own cell.
+pub fn open_file_key(file_metadata: &FileMetadata) -> ExternResult<Option<XSalsa20Poly1305KeyRef>> {
+    let encryption = match &file_metadata.encryption {
+        Some(encryption) => encryption,
+        None => return Ok(None),
+    };
+
+    if share_caller()?.is_some() {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Encrypted files can only be decrypted by the agents they are encrypted for"
+        ))));
+    }
+
+    let my_pub_key = agent_info()?.agent_initial_pubkey;
+    let wrapped_key = encryption
+        .wrapped_keys
+        .iter()
+        .find(|wrapped_key| wrapped_key.agent == my_pub_key)
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "File is not encrypted for this agent"
+        ))))?;
+
+    let key_ref = x_salsa20_poly1305_shared_secret_ingest(
+        wrapped_key.recipient,
+        encryption.sender,
+        wrapped_key.wrapped_key.clone(),
+        None,
+    )?;
+    Ok(Some(key_ref))
+}
+
+/// Encrypts the content of a chunk, returning the nonce followed by the encrypted content.
+pub fn encrypt_chunk(key_ref: &XSalsa20Poly1305KeyRef, content: Vec<u8>) -> ExternResult<Vec<u8>> {
+    let encrypted_data = x_salsa20_poly1305_encrypt(key_ref.clone(), content.into())?;
+
+    let mut bytes = encrypted_data.as_nonce_ref().as_ref().to_vec();
+    bytes.extend_from_slice(encrypted_data.as_encrypted_data_ref());
+    Ok(bytes)
+}
+
+/// Decrypts a chunk made of a nonce followed by the encrypted content.
+pub fn decrypt_chunk(key_ref: &XSalsa20Poly1305KeyRef, bytes: &[u8]) -> ExternResult<Vec<u8>> {
+    if bytes.len() < ENCRYPTED_CHUNK_OVERHEAD {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Encrypted chunk is too short"
+        ))));
+    }
+
+    let nonce = XSalsa20Poly1305Nonce::try_from(&bytes[..NONCE_BYTES])
+        .map_err(|_| wasm_error!(WasmErrorInner::Guest(String::from("Invalid chunk nonce"))))?;
+    let encrypted_data = XSalsa20Poly1305EncryptedData::new(nonce, bytes[NONCE_BYTES..].to_vec());
+
+    let content =
+        x_salsa20_poly1305_decrypt(key_ref.clone(), encrypted_data)?.ok_or(wasm_error!(
+            WasmErrorInner::Guest(String::from("Could not decrypt the file chunk"))
+        ))?;
+    Ok(content.as_ref().to_vec())
+}
+
+/// Returns the X25519 key published by the calling agent, read from their source chain.
+fn get_my_encryption_key() -> ExternResult<X25519PubKey> {
+    let record = query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::EncryptionKey.try_into()?)
+            .include_entries(true),
+    )?
+    .pop()
+    .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+        "Could not find the encryption key of the agent"
+    ))))?;
+
+    Ok(EncryptionKey::try_from(record)?.0)
+}
@@ -0,0 +1,46 @@
+//! This module defines the errors returned by the File Storage zome that clients may need to tell apart
+//! from the generic guest errors.
+
+use hdk::prelude::*;
+
+/// Errors returned by the File Storage zome.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileSystemError {
+    /// The requested chunk index is not lower than the number of chunks of the file.
+    ChunkIndexOutOfRange { index: usize, chunks_count: usize },
+    /// Storing a new file would exceed the maximum number of files of the agent.
+    FilesQuotaExceeded { max_files: usize },
+    /// Storing the content of a file would exceed the maximum number of bytes of the agent.
+    BytesQuotaExceeded { bytes: usize, max_bytes: usize },
+}
+
+impl std::fmt::Display for FileSystemError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileSystemError::ChunkIndexOutOfRange {
+                index,
+                chunks_count,
+            } => write!(
+                f,
+                "Chunk index out of range: index {} but the file has {} chunks",
+                index, chunks_count
+            ),
+            FileSystemError::FilesQuotaExceeded { max_files } => write!(
+                f,
+                "Storage quota exceeded: the agent cannot store more than {} files",
+                max_files
+            ),
+            FileSystemError::BytesQuotaExceeded { bytes, max_bytes } => write!(
+                f,
+                "Storage quota exceeded: {} bytes needed but the agent cannot store more than {} bytes",
+                bytes, max_bytes
+            ),
+        }
+    }
+}
+
+impl From<FileSystemError> for WasmError {
+    fn from(error: FileSystemError) -> Self {
+        wasm_error!(WasmErrorInner::Guest(error.to_string()))
+    }
+}
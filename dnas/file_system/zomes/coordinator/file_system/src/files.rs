@@ -2,17 +2,41 @@
 //! operations such as creating, updating, and retrieving file metadata and chunks.
 //! It also provides utility functions for handling file paths and chunking files.
 
-use crate::get_file_metadata;
+use crate::compression::{compress_chunk, decompress_chunk};
+use crate::encryption::{decrypt_chunk, encrypt_chunk, open_file_key};
+use crate::private_files::*;
+use crate::quotas::check_storage_quota;
+use crate::sharing::share_caller;
 use file_system_integrity::*;
+use hdk::hash_path::path::Component;
 use hdk::prelude::*;
 use regex::Regex;
+use std::collections::HashSet;
 use std::path::{Path as FileSystemPath, PathBuf};
 
+/// Pseudo-random values for each byte, used by the gear rolling hash of the content-defined chunking.
+const GEAR: [u64; 256] = gear_table();
+
 /// Retrieves file metadata for all files within a given directory path and its subdirectories.
 pub fn get_files_metadata_recursively(path: Path) -> ExternResult<Vec<Record>> {
+    let mut files = get_files_metadata(path.clone())?;
+
+    let typed_path = path.typed(LinkTypes::PathFileSystem)?;
+    let sub_folders_paths = typed_path.children_paths().unwrap_or_default();
+
+    for path in sub_folders_paths {
+        let sub_folder_files = get_files_metadata_recursively(path.path)?;
+        files.extend(sub_folder_files)
+    }
+
+    Ok(files)
+}
+
+/// Retrieves file metadata for the files directly within a given directory path, without its subdirectories.
+pub fn get_files_metadata(path: Path) -> ExternResult<Vec<Record>> {
     let mut files = Vec::new();
 
-    let typed_path = path.clone().typed(LinkTypes::PathFileSystem)?;
+    let typed_path = path.typed(LinkTypes::PathFileSystem)?;
 
     let files_links = get_links(
         typed_path.path_entry_hash()?,
@@ -21,19 +45,12 @@ pub fn get_files_metadata_recursively(path: Path) -> ExternResult<Vec<Record>> {
     )?;
 
     for link in files_links {
-        let file_metadata = get_file_metadata(ActionHash::from(link.clone().target))?;
+        let file_metadata = get_latest_file_metadata(ActionHash::from(link.clone().target))?;
         if let Some(file_metadata) = file_metadata {
             files.push(file_metadata);
         }
     }
 
-    let sub_folders_paths = typed_path.children_paths().unwrap_or_default();
-
-    for path in sub_folders_paths {
-        let sub_folder_files = get_files_metadata_recursively(path.path)?;
-        files.extend(sub_folder_files)
-    }
-
     Ok(files)
 }
 
@@ -41,7 +58,7 @@ pub fn get_files_metadata_recursively(path: Path) -> ExternResult<Vec<Record>> {
 pub fn create_file_chunk(file_chunk: FileChunk) -> ExternResult<Record> {
     let file_chunk_hash = hash_entry(&file_chunk)?;
 
-    if let None = get(file_chunk_hash.clone(), GetOptions::default())? {
+    if get(file_chunk_hash.clone(), GetOptions::default())?.is_none() {
         create_entry(&EntryTypes::FileChunk(file_chunk))?;
     }
 
@@ -52,16 +69,18 @@ pub fn create_file_chunk(file_chunk: FileChunk) -> ExternResult<Record> {
     Ok(record)
 }
 
-/// Creates a new file metadata entry and stores it in the DHT.
+/// Creates a new file metadata entry and stores it in the DHT, if the storage quotas of the agent allow it.
 pub fn create_file_metadata(file_metadata: FileMetadata) -> ExternResult<Record> {
+    check_storage_quota(&file_metadata, true)?;
+
     let action_hash = create_entry(&EntryTypes::FileMetadata(file_metadata.clone()))?;
-    let record =
-        get_file_metadata(action_hash.clone())?.ok_or(wasm_error!(WasmErrorInner::Guest(
-            String::from("Could not find the newly created file metadata")
-        )))?;
+    let record = get_latest_file_metadata(action_hash.clone())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from(
+            "Could not find the newly created file metadata"
+        ))
+    ))?;
 
-    let file_path = fs_path_to_dht_path(file_metadata.path.as_str());
-    let path = Path::from(file_path);
+    let path = fs_path_to_dht_path(file_metadata.path.as_str(), &file_system_properties()?);
     let typed_path = path.typed(LinkTypes::PathFileSystem)?;
     typed_path.ensure()?;
 
@@ -72,21 +91,44 @@ pub fn create_file_metadata(file_metadata: FileMetadata) -> ExternResult<Record>
         (),
     )?;
 
+    link_file_chunks_to_file_metadata(&file_metadata.chunks_hashes, action_hash)?;
+
     Ok(record)
 }
 
-/// Retrieves a file chunk by its hash from the DHT.
+/// Retrieves a file chunk by its hash from the DHT or, on the calls made by the agent of this cell, from their
+/// private chunks.
 pub fn get_file_chunk(file_chunk_hash: EntryHash) -> ExternResult<Record> {
-    let record = get(file_chunk_hash, GetOptions::default())?
-        .ok_or(wasm_error!(WasmErrorInner::Guest("Chunk not found".into())))?;
+    let share_call = share_caller()?.is_some();
 
-    Ok(record)
+    if let Some(record) = get(file_chunk_hash.clone(), GetOptions::default())? {
+        if record.entry().as_option().is_some() && !(share_call && is_private_record(&record)) {
+            return Ok(record);
+        }
+    }
+
+    let record = match share_call {
+        true => None,
+        false => get_private_file_chunk(file_chunk_hash)?,
+    };
+
+    record.ok_or(wasm_error!(WasmErrorInner::Guest("Chunk not found".into())))
 }
 
-/// Retrieves file metadata by path and name from the DHT.
+/// Retrieves file metadata by path and name from the DHT, or from the private files of the calling agent, comparing
+/// names by their name key.
 pub fn get_file_metadata_by_path_and_name(path: String, name: String) -> ExternResult<Record> {
-    let file_path = fs_path_to_dht_path(path.as_str());
-    let path = Path::from(file_path);
+    let properties = file_system_properties()?;
+    let name_key = properties.name_key(&name);
+
+    for file_metadata_record in get_private_files_metadata_by_path(&path, false)? {
+        let file_metadata: FileMetadata = file_metadata_record.clone().try_into()?;
+        if properties.name_key(&file_metadata.name) == name_key {
+            return Ok(file_metadata_record);
+        }
+    }
+
+    let path = fs_path_to_dht_path(path.as_str(), &properties);
     let typed_path = path.typed(LinkTypes::PathFileSystem)?;
     let files_links = get_links(
         typed_path.path_entry_hash()?,
@@ -95,12 +137,13 @@ pub fn get_file_metadata_by_path_and_name(path: String, name: String) -> ExternR
     )?;
 
     for link in files_links {
-        let file_metadata_record = get_file_metadata(ActionHash::from(link.clone().target))?
-            .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
-                "Could not find the newly created file metadata"
-            ))))?;
+        let file_metadata_record =
+            match get_latest_file_metadata(ActionHash::from(link.clone().target))? {
+                Some(record) => record,
+                None => continue,
+            };
         let file_metadata: FileMetadata = file_metadata_record.clone().try_into()?;
-        if file_metadata.name == name {
+        if properties.name_key(&file_metadata.name) == name_key {
             return Ok(file_metadata_record);
         }
     }
@@ -108,17 +151,149 @@ pub fn get_file_metadata_by_path_and_name(path: String, name: String) -> ExternR
     Err(wasm_error!(WasmErrorInner::Guest("File not found".into())))
 }
 
+/// Returns the hashes of the actions deleted by the source chain of the calling agent.
+pub fn get_deleted_actions_hashes() -> ExternResult<HashSet<ActionHash>> {
+    Ok(
+        query(ChainQueryFilter::new().action_type(ActionType::Delete))?
+            .into_iter()
+            .filter_map(|record| match record.action() {
+                Action::Delete(delete) => Some(delete.deletes_address.clone()),
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
+/// Retrieves the latest version of a file metadata entry for the specified hash, from the DHT or, on the calls made by
+/// the agent of this cell, from their private files.
+pub fn get_latest_file_metadata(
+    original_file_metadata_hash: ActionHash,
+) -> ExternResult<Option<Record>> {
+    let latest_file_metadata_hash =
+        get_latest_file_metadata_update_hash(original_file_metadata_hash.clone())?
+            .unwrap_or(original_file_metadata_hash.clone());
+
+    match get(latest_file_metadata_hash, GetOptions::default())? {
+        Some(record) if !is_private_record(&record) => Ok(Some(record)),
+        _ => match share_caller()? {
+            Some(_) => Ok(None),
+            None => get_private_file_metadata(&original_file_metadata_hash),
+        },
+    }
+}
+
+/// Retrieves the hash of the latest update of a file metadata entry, or `None` if it was never updated.
+pub fn get_latest_file_metadata_update_hash(
+    original_file_metadata_hash: ActionHash,
+) -> ExternResult<Option<ActionHash>> {
+    let update_links = get_links(
+        original_file_metadata_hash,
+        LinkTypes::FileMetaDataUpdate,
+        None,
+    )?;
+    let latest_link = update_links
+        .into_iter()
+        .max_by(|link_a, link_b| link_a.timestamp.cmp(&link_b.timestamp));
+
+    Ok(latest_link.map(|link| ActionHash::from(link.target)))
+}
+
+/// Retrieves the original file metadata record and all its updates, ordered by timestamp.
+pub fn get_file_metadata_versions(
+    original_file_metadata_hash: ActionHash,
+) -> ExternResult<Vec<Record>> {
+    let mut versions = Vec::new();
+
+    match get(original_file_metadata_hash.clone(), GetOptions::default())? {
+        Some(record) => versions.push(record),
+        None => return Ok(versions),
+    }
+
+    let update_links = get_links(
+        original_file_metadata_hash,
+        LinkTypes::FileMetaDataUpdate,
+        None,
+    )?;
+    for link in update_links {
+        if let Some(record) = get(ActionHash::from(link.target), GetOptions::default())? {
+            versions.push(record);
+        }
+    }
+
+    versions.sort_by(|record_a, record_b| {
+        record_a
+            .action()
+            .timestamp()
+            .cmp(&record_b.action().timestamp())
+    });
+
+    Ok(versions)
+}
+
+/// Moves the link pointing to a file metadata entry from one directory path to another,
+/// given the file metadata version that placed the file at the destination path.
+pub fn move_file_metadata_link(
+    original_file_metadata_hash: ActionHash,
+    file_metadata_hash: ActionHash,
+    from_path: &str,
+    to_path: &str,
+) -> ExternResult<()> {
+    unlink_file_metadata_from_path(original_file_metadata_hash.clone(), from_path)?;
+    link_file_metadata_to_path(original_file_metadata_hash, file_metadata_hash, to_path)
+}
+
+/// Links a file metadata entry to a directory path, creating the path if needed. The link is tagged with the
+/// file metadata version holding this path, so it can be validated.
+pub fn link_file_metadata_to_path(
+    original_file_metadata_hash: ActionHash,
+    file_metadata_hash: ActionHash,
+    path: &str,
+) -> ExternResult<()> {
+    let typed_path =
+        fs_path_to_dht_path(path, &file_system_properties()?).typed(LinkTypes::PathFileSystem)?;
+    typed_path.ensure()?;
+
+    create_link(
+        typed_path.path_entry_hash()?,
+        original_file_metadata_hash,
+        LinkTypes::PathToFileMetaData,
+        file_metadata_version_tag(&file_metadata_hash),
+    )?;
+
+    Ok(())
+}
+
+/// Removes the links pointing to a file metadata entry from a directory path.
+pub fn unlink_file_metadata_from_path(
+    original_file_metadata_hash: ActionHash,
+    path: &str,
+) -> ExternResult<()> {
+    let typed_path =
+        fs_path_to_dht_path(path, &file_system_properties()?).typed(LinkTypes::PathFileSystem)?;
+    let files_links = get_links(
+        typed_path.path_entry_hash()?,
+        LinkTypes::PathToFileMetaData,
+        None,
+    )?;
+    for link in files_links {
+        if link.target == original_file_metadata_hash.clone().into() {
+            delete_link(link.create_link_hash)?;
+        }
+    }
+
+    Ok(())
+}
+
 /// Updates the file metadata for a given file.
 pub fn update_file_metadata(
     original_file_metadata_hash: ActionHash,
     previous_file_metadata_hash: Option<ActionHash>,
     file_metadata: FileMetadata,
 ) -> ExternResult<Record> {
-    let file_metadata_hash = if previous_file_metadata_hash.is_some() {
-        previous_file_metadata_hash.unwrap()
-    } else {
-        original_file_metadata_hash.clone()
-    };
+    check_storage_quota(&file_metadata, false)?;
+
+    let file_metadata_hash =
+        previous_file_metadata_hash.unwrap_or_else(|| original_file_metadata_hash.clone());
     let updated_metadata_hash = update_entry(file_metadata_hash, &file_metadata.clone())?;
 
     create_link(
@@ -128,56 +303,388 @@ pub fn update_file_metadata(
         (),
     )?;
 
-    let record = get_file_metadata(updated_metadata_hash.clone())?.ok_or(wasm_error!(
+    link_file_chunks_to_file_metadata(&file_metadata.chunks_hashes, updated_metadata_hash.clone())?;
+
+    let record = get_latest_file_metadata(updated_metadata_hash.clone())?.ok_or(wasm_error!(
         WasmErrorInner::Guest(String::from("Could not find the file metadata"))
     ))?;
 
     Ok(record)
 }
 
-/// Splits the file content into chunks and returns a vector of their hashes.
-pub fn chunk_file(file_content: Vec<u8>) -> ExternResult<Vec<EntryHash>> {
-    let chunk_size = 1024 * 1024; // 1 MB
-    let num_chunks = (file_content.len() as f64 / chunk_size as f64).ceil() as usize;
-    let mut chunks_hashes = Vec::new();
+/// Links each distinct file chunk to the file metadata entry referencing it, so shared chunks are only
+/// deleted once no file metadata references them anymore.
+pub fn link_file_chunks_to_file_metadata(
+    chunks_hashes: &[EntryHash],
+    file_metadata_hash: ActionHash,
+) -> ExternResult<()> {
+    let mut linked_chunks_hashes: Vec<&EntryHash> = Vec::new();
+
+    for chunk_hash in chunks_hashes {
+        if linked_chunks_hashes.contains(&chunk_hash) {
+            continue;
+        }
+        create_link(
+            chunk_hash.clone(),
+            file_metadata_hash.clone(),
+            LinkTypes::FileChunkToFileMetaData,
+            (),
+        )?;
+        linked_chunks_hashes.push(chunk_hash);
+    }
 
-    for i in 0..num_chunks {
-        let start = i * chunk_size;
-        let end = std::cmp::min((i + 1) * chunk_size, file_content.len());
-        let chunk_data = file_content[start..end].to_vec();
+    Ok(())
+}
+
+/// Removes the references from a file chunk to the specified file metadata entries, then deletes the creations of the
+/// file chunk authored by the calling agent if no other live file metadata entry still references it. Returns the
+/// hashes of the delete actions.
+pub fn release_file_chunk(
+    chunk_hash: EntryHash,
+    file_metadata_hashes: &[ActionHash],
+) -> ExternResult<Vec<ActionHash>> {
+    let mut delete_actions: Vec<ActionHash> = Vec::new();
+    let mut referenced = false;
+
+    let reference_links = get_links(chunk_hash.clone(), LinkTypes::FileChunkToFileMetaData, None)?;
+    for link in reference_links {
+        let file_metadata_hash = ActionHash::from(link.target);
+        if file_metadata_hashes.contains(&file_metadata_hash) {
+            delete_actions.push(delete_link(link.create_link_hash)?);
+        } else if get(file_metadata_hash, GetOptions::default())?.is_some() {
+            referenced = true;
+        }
+    }
+
+    if referenced {
+        return Ok(delete_actions);
+    }
+
+    // Other agents storing the same chunk keep their own creation of it
+    if let Some(Details::Entry(entry_details)) = get_details(chunk_hash, GetOptions::default())? {
+        let my_pub_key = agent_info()?.agent_initial_pubkey;
+        let deleted_actions_hashes: HashSet<ActionHash> = entry_details
+            .deletes
+            .iter()
+            .filter_map(|delete| match delete.action() {
+                Action::Delete(delete) => Some(delete.deletes_address.clone()),
+                _ => None,
+            })
+            .collect();
+
+        for create in entry_details.actions {
+            if create.action().author() == &my_pub_key
+                && !deleted_actions_hashes.contains(create.as_hash())
+            {
+                delete_actions.push(delete_entry(create.as_hash().clone())?);
+            }
+        }
+    }
+
+    Ok(delete_actions)
+}
+
+/// Splits the file content into content-defined chunks, encodes them for storage, stores them on the DHT, or as
+/// private entries on the source chain, and returns their hashes, sizes and checksum as stored, along with the content
+/// size of each chunk and the checksum of the content unless the chunks are encrypted.
+pub fn chunk_file(
+    file_content: Vec<u8>,
+    encoding: &ChunkEncoding,
+    private: bool,
+) -> ExternResult<StoredChunks> {
+    let properties = file_system_properties()?;
+    let mut chunks_hashes = Vec::new();
+    let mut chunks_sizes = Vec::new();
+    let mut content_sizes = Vec::new();
+    let mut stored_hasher = ContentHasher::default();
+    let mut content_hasher = ContentHasher::default();
+    let mut start = 0;
+
+    for chunk_length in content_defined_chunks_lengths(&file_content, &properties) {
+        let end = start + chunk_length;
+        content_hasher.update(&file_content[start..end]);
+        let chunk_data = encoding.encode(&file_content[start..end])?;
+        chunks_sizes.push(chunk_data.len());
+        content_sizes.push(chunk_length);
+        stored_hasher.update(&chunk_data);
 
         let file_chunk = FileChunk(SerializedBytes::from(UnsafeBytes::from(chunk_data)));
 
-        create_file_chunk(file_chunk.clone())?;
+        if private {
+            create_private_file_chunk(file_chunk.clone())?;
+        } else {
+            create_file_chunk(file_chunk.clone())?;
+        }
         let chunk_hash = hash_entry(&file_chunk)?;
         chunks_hashes.push(chunk_hash);
+
+        start = end;
     }
 
-    Ok(chunks_hashes)
+    // The content of an encrypted file must not be revealed by its metadata
+    let (content_sizes, content_checksum) = match encoding.key_ref {
+        Some(_) => (Vec::new(), None),
+        None => (content_sizes, Some(content_hasher.finalize())),
+    };
+
+    Ok(StoredChunks {
+        chunks_hashes,
+        stored_size: chunks_sizes.iter().sum(),
+        chunks_sizes,
+        content_sizes,
+        checksum: stored_hasher.finalize(),
+        content_checksum,
+    })
+}
+
+/// Hashes, sizes and checksum of the chunks stored for a file content, after compression and encryption, and
+/// sizes and checksum of the content itself, which are not set for encrypted files.
+pub struct StoredChunks {
+    pub chunks_hashes: Vec<EntryHash>,
+    pub chunks_sizes: Vec<usize>,
+    pub content_sizes: Vec<usize>,
+    pub stored_size: usize,
+    pub checksum: String,
+    pub content_checksum: Option<String>,
 }
 
-/// Converts a filesystem-style path to a DHT-style path.
-pub fn fs_path_to_dht_path(path: &str) -> String {
-    let mut path = standardize_fs_path(path);
-    path.remove(0);
+/// How the content of the chunks of a file is stored: compressed with its codec if any, then encrypted with its key
+/// if any.
+pub struct ChunkEncoding {
+    pub compression: Option<CompressionCodec>,
+    pub key_ref: Option<XSalsa20Poly1305KeyRef>,
+    max_chunk_size: usize,
+}
+
+impl ChunkEncoding {
+    /// Creates the encoding of chunks compressed with the given codec and encrypted with the given key.
+    pub fn new(
+        compression: Option<CompressionCodec>,
+        key_ref: Option<XSalsa20Poly1305KeyRef>,
+    ) -> ExternResult<Self> {
+        Ok(ChunkEncoding {
+            compression,
+            key_ref,
+            max_chunk_size: file_system_properties()?.max_chunk_size(),
+        })
+    }
 
-    let mut path_parts = path.split("/").collect::<Vec<&str>>();
-    if path_parts[0] == "" {
-        path_parts.remove(0);
+    /// Returns the encoding of the chunks of a file, unwrapping its key if it is encrypted.
+    pub fn for_file(file_metadata: &FileMetadata) -> ExternResult<Self> {
+        ChunkEncoding::new(file_metadata.compression, open_file_key(file_metadata)?)
     }
-    path_parts.insert(0, "root");
-    path_parts.join(".")
+
+    /// Encodes the content of a chunk for storage.
+    pub fn encode(&self, content: &[u8]) -> ExternResult<Vec<u8>> {
+        let content = match self.compression {
+            Some(codec) => compress_chunk(codec, content),
+            None => content.to_vec(),
+        };
+        match &self.key_ref {
+            Some(key_ref) => encrypt_chunk(key_ref, content),
+            None => Ok(content),
+        }
+    }
+
+    /// Decodes a stored chunk back to its content.
+    pub fn decode(&self, bytes: &[u8]) -> ExternResult<Vec<u8>> {
+        let bytes = match &self.key_ref {
+            Some(key_ref) => decrypt_chunk(key_ref, bytes)?,
+            None => bytes.to_vec(),
+        };
+        match self.compression {
+            Some(codec) => decompress_chunk(codec, &bytes, self.max_chunk_size),
+            None => Ok(bytes),
+        }
+    }
+
+    /// Returns a file chunk record holding the content of the stored chunk, or the record itself if the chunks are
+    /// stored as is.
+    pub fn decode_record(&self, record: Record) -> ExternResult<Record> {
+        if self.compression.is_none() && self.key_ref.is_none() {
+            return Ok(record);
+        }
+
+        let file_chunk = FileChunk::try_from(record.clone())?;
+        let content = self.decode(file_chunk.0.bytes())?;
+        let entry = Entry::try_from(FileChunk(SerializedBytes::from(UnsafeBytes::from(content))))?;
+
+        Ok(Record::new(record.signed_action, Some(entry)))
+    }
+}
+
+/// Sizes and boundary masks of the content-defined chunking, derived from the configured average chunk size.
+struct ChunkingParameters {
+    min_chunk_size: usize,
+    average_chunk_size: usize,
+    max_chunk_size: usize,
+    /// Chunk boundary mask used before the average chunk size is reached, harder to match.
+    small_chunk_mask: u64,
+    /// Chunk boundary mask used after the average chunk size is reached, easier to match.
+    large_chunk_mask: u64,
+}
+
+impl From<&FileSystemProperties> for ChunkingParameters {
+    fn from(properties: &FileSystemProperties) -> Self {
+        let average_bits = usize::BITS - 1 - properties.average_chunk_size.leading_zeros();
+
+        ChunkingParameters {
+            min_chunk_size: properties.min_chunk_size(),
+            average_chunk_size: properties.average_chunk_size,
+            max_chunk_size: properties.max_chunk_size(),
+            small_chunk_mask: top_bits_mask(average_bits + 2),
+            large_chunk_mask: top_bits_mask(average_bits - 2),
+        }
+    }
+}
+
+/// Returns the lengths of the chunks of the content, cut at boundaries defined by the content itself.
+/// Inserting or removing bytes only changes the chunks around the edit, so the other chunks keep the same hash.
+pub fn content_defined_chunks_lengths(
+    content: &[u8],
+    properties: &FileSystemProperties,
+) -> Vec<usize> {
+    let parameters = ChunkingParameters::from(properties);
+    let mut chunks_lengths = Vec::new();
+    let mut start = 0;
+
+    while start < content.len() {
+        let chunk_length = next_chunk_length(&content[start..], &parameters);
+        chunks_lengths.push(chunk_length);
+        start += chunk_length;
+    }
+
+    chunks_lengths
 }
 
-/// Returns a standardized filesystem path by converting backslashes to forward slashes and removing redundant separators.
+/// Returns the length of the first chunk of the content, using a FastCDC-style gear rolling hash
+/// with normalized chunking around the average chunk size.
+fn next_chunk_length(content: &[u8], parameters: &ChunkingParameters) -> usize {
+    if content.len() <= parameters.min_chunk_size {
+        return content.len();
+    }
+
+    let end = std::cmp::min(content.len(), parameters.max_chunk_size);
+    let normal_end = std::cmp::min(end, parameters.average_chunk_size);
+    let mut hash: u64 = 0;
+
+    for (i, byte) in content
+        .iter()
+        .enumerate()
+        .take(end)
+        .skip(parameters.min_chunk_size)
+    {
+        hash = (hash << 1).wrapping_add(GEAR[*byte as usize]);
+        let mask = if i < normal_end {
+            parameters.small_chunk_mask
+        } else {
+            parameters.large_chunk_mask
+        };
+        if hash & mask == 0 {
+            return i + 1;
+        }
+    }
+
+    end
+}
+
+/// Returns a mask selecting the given number of most significant bits,
+/// which depend on the most bytes of the gear rolling hash window.
+const fn top_bits_mask(bits: u32) -> u64 {
+    !0u64 << (64 - bits)
+}
+
+/// Generates the gear table with the splitmix64 generator, so every agent computes the same chunk boundaries.
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0;
+    let mut i = 0;
+
+    while i < 256 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut value = state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        table[i] = value ^ (value >> 31);
+        i += 1;
+    }
+
+    table
+}
+
+/// Maps the byte range `[start, end)` of a file onto its chunks, given their sizes.
+/// Returns, for each overlapping chunk, its index and the range of bytes to read within it.
+pub fn chunks_for_range(
+    chunks_sizes: &[usize],
+    start: usize,
+    end: usize,
+) -> Vec<(usize, usize, usize)> {
+    let mut chunks = Vec::new();
+    let mut chunk_start = 0;
+
+    for (index, chunk_size) in chunks_sizes.iter().enumerate() {
+        let chunk_end = chunk_start + chunk_size;
+        if chunk_start >= end {
+            break;
+        }
+        if chunk_end > start {
+            let from = start.saturating_sub(chunk_start);
+            let to = std::cmp::min(end, chunk_end) - chunk_start;
+            chunks.push((index, from, to));
+        }
+        chunk_start = chunk_end;
+    }
+
+    chunks
+}
+
+/// Converts a filesystem-style path to a DHT-style path, with one path component per folder under the `root`
+/// component. Components are built explicitly, so folder names can contain dots, and hold the name key of each
+/// folder, so folders differing only by their case are the same folder in case-insensitive mode.
+pub fn fs_path_to_dht_path(path: &str, properties: &FileSystemProperties) -> Path {
+    let path = standardize_fs_path(path);
+
+    let components: Vec<Component> = std::iter::once(String::from("root"))
+        .chain(
+            path.split('/')
+                .filter(|part| !part.is_empty())
+                .map(|part| properties.name_key(part)),
+        )
+        .map(|part| Component::from(part.as_str()))
+        .collect();
+    Path::from(components)
+}
+
+/// Converts a DHT-style path built by `fs_path_to_dht_path` back to a filesystem-style path.
+pub fn dht_path_to_fs_path(path: &Path) -> ExternResult<String> {
+    let components: &Vec<Component> = path.as_ref();
+    let mut fs_path = String::new();
+
+    for component in components.iter().skip(1) {
+        let name = String::try_from(component).map_err(|e| wasm_error!(e))?;
+        fs_path.push('/');
+        fs_path.push_str(&name);
+    }
+
+    if fs_path.is_empty() {
+        fs_path.push('/');
+    }
+    Ok(fs_path)
+}
+
+/// Joins a name to a filesystem-style directory path and returns the standardized result.
+pub fn join_fs_path(path: &str, name: &str) -> String {
+    standardize_fs_path(&format!("{}/{}", path, name))
+}
+
+/// Returns a standardized filesystem path by normalizing it to Unicode NFC, converting backslashes to forward slashes
+/// and removing redundant separators.
 pub fn standardize_fs_path(path: &str) -> String {
-    let path = path.replace("\\", "/");
+    let path = normalize_name(path).replace("\\", "/");
     let re = Regex::new(r"[/]+").unwrap();
     let path = re.replace_all(&path, "/").to_string();
     let mut path = PathBuf::from(path);
 
-    println!("path: {:?}", path);
-
     let main_separator = std::path::MAIN_SEPARATOR.to_string();
 
     if !path.has_root() {
@@ -186,7 +693,7 @@ pub fn standardize_fs_path(path: &str) -> String {
 
     let path_str = path.to_str().unwrap();
     let trimmed_path = path_str.trim_end_matches(&main_separator);
-    if trimmed_path == "" {
+    if trimmed_path.is_empty() {
         return main_separator;
     }
 
@@ -200,21 +707,249 @@ mod tests {
     #[test]
     fn test_fs_path_to_dht_path() {
         let cases = vec![
-            ("/test/path", "root.test.path"),
-            ("test/path", "root.test.path"),
-            ("/test//path", "root.test.path"),
-            ("/test/path/", "root.test.path"),
-            ("", "root"),
-            ("/", "root"),
-            ("///", "root"),
+            ("/test/path", vec!["root", "test", "path"]),
+            ("test/path", vec!["root", "test", "path"]),
+            ("/test//path", vec!["root", "test", "path"]),
+            ("/test/path/", vec!["root", "test", "path"]),
+            ("/v1.2/.config", vec!["root", "v1.2", ".config"]),
+            ("/cafe\u{301}", vec!["root", "caf\u{e9}"]),
+            ("", vec!["root"]),
+            ("/", vec!["root"]),
+            ("///", vec!["root"]),
         ];
 
         for (input, expected) in cases {
-            let dht_path = fs_path_to_dht_path(input);
+            let dht_path = fs_path_to_dht_path(input, &FileSystemProperties::default());
+            let expected = Path::from(
+                expected
+                    .into_iter()
+                    .map(Component::from)
+                    .collect::<Vec<Component>>(),
+            );
             assert_eq!(dht_path, expected, "Failed for input: {:?}", input);
         }
     }
 
+    #[test]
+    fn test_fs_path_to_dht_path_case_insensitive() {
+        let properties = FileSystemProperties {
+            case_insensitive: true,
+            ..FileSystemProperties::default()
+        };
+        let cases = vec![
+            "/Documents/CAFÉ",
+            "/documents/café",
+            "/DOCUMENTS/CAFE\u{301}",
+        ];
+
+        for input in cases {
+            assert_eq!(
+                fs_path_to_dht_path(input, &properties),
+                fs_path_to_dht_path("/documents/café", &FileSystemProperties::default()),
+                "Failed for input: {:?}",
+                input
+            );
+        }
+        assert_ne!(
+            fs_path_to_dht_path("/Documents", &FileSystemProperties::default()),
+            fs_path_to_dht_path("/documents", &FileSystemProperties::default())
+        );
+    }
+
+    #[test]
+    fn test_dht_path_to_fs_path() {
+        let cases = vec![
+            ("/", "/"),
+            ("/test/path", "/test/path"),
+            ("test\\path\\", "/test/path"),
+            ("/v1.2/.config/a.b.c", "/v1.2/.config/a.b.c"),
+            ("/données/été", "/données/été"),
+        ];
+
+        for (input, expected) in cases {
+            let fs_path = dht_path_to_fs_path(&fs_path_to_dht_path(
+                input,
+                &FileSystemProperties::default(),
+            ))
+            .unwrap();
+            assert_eq!(fs_path, expected, "Failed for input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_file_system_path_entry() {
+        let cases = [
+            "/",
+            "/test",
+            "/test/path",
+            "/documents/été/ünïcode",
+            "/v1.2/.config",
+        ];
+
+        let case_insensitive_properties = FileSystemProperties {
+            case_insensitive: true,
+            ..FileSystemProperties::default()
+        };
+        for properties in [FileSystemProperties::default(), case_insensitive_properties] {
+            for input in cases.iter() {
+                let path = fs_path_to_dht_path(input, &properties);
+                let expected = Entry::App(AppEntryBytes(SerializedBytes::try_from(path).unwrap()));
+                assert_eq!(
+                    file_system_path_entry(input, &properties).unwrap(),
+                    expected,
+                    "Failed for input: {:?}",
+                    input
+                );
+            }
+        }
+    }
+
+    /// Generates deterministic pseudo-random content with the xorshift64 generator.
+    fn pseudo_random_content(length: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..length)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_content_defined_chunks_lengths() {
+        let properties = FileSystemProperties::default();
+        assert!(content_defined_chunks_lengths(&[], &properties).is_empty());
+        assert_eq!(
+            content_defined_chunks_lengths(&[1, 2, 3], &properties),
+            vec![3]
+        );
+
+        let small_chunks_properties = FileSystemProperties {
+            average_chunk_size: 4096,
+            ..FileSystemProperties::default()
+        };
+        let cases = vec![
+            (vec![0; 5 * 1024 * 1024], &properties),
+            (pseudo_random_content(8 * 1024 * 1024, 42), &properties),
+            (
+                pseudo_random_content(256 * 1024, 5),
+                &small_chunks_properties,
+            ),
+        ];
+
+        for (content, properties) in cases {
+            let chunks_lengths = content_defined_chunks_lengths(&content, properties);
+            assert_eq!(chunks_lengths.iter().sum::<usize>(), content.len());
+            assert_eq!(
+                chunks_lengths,
+                content_defined_chunks_lengths(&content, properties)
+            );
+
+            let (last, others) = chunks_lengths.split_last().unwrap();
+            assert!(*last <= properties.max_chunk_size());
+            assert!(others.len() > 1);
+            for chunk_length in others {
+                assert!(*chunk_length >= properties.min_chunk_size());
+                assert!(*chunk_length <= properties.max_chunk_size());
+            }
+        }
+    }
+
+    #[test]
+    fn test_content_defined_chunking_after_insertion() {
+        let content = pseudo_random_content(8 * 1024 * 1024, 7);
+        let mut edited_content = vec![42];
+        edited_content.extend_from_slice(&content);
+
+        let chunks = |content: &[u8]| {
+            let mut start = 0;
+            content_defined_chunks_lengths(content, &FileSystemProperties::default())
+                .into_iter()
+                .map(|length| {
+                    start += length;
+                    content[start - length..start].to_vec()
+                })
+                .collect::<Vec<Vec<u8>>>()
+        };
+        let original_chunks = chunks(&content);
+        let edited_chunks = chunks(&edited_content);
+
+        let new_chunks = edited_chunks
+            .iter()
+            .filter(|chunk| !original_chunks.contains(chunk))
+            .count();
+        assert!(original_chunks.len() > 2);
+        assert!(new_chunks <= 2, "{} chunks changed", new_chunks);
+    }
+
+    #[test]
+    fn test_content_hasher() {
+        let checksum = |content: &[u8]| {
+            let mut content_hasher = ContentHasher::default();
+            content_hasher.update(content);
+            content_hasher.finalize()
+        };
+        let content = pseudo_random_content(3 * 1024 * 1024, 3);
+        let content_checksum = checksum(&content);
+        assert_eq!(content_checksum.len(), 64);
+
+        let mut content_hasher = ContentHasher::default();
+        let mut start = 0;
+        for length in content_defined_chunks_lengths(&content, &FileSystemProperties::default()) {
+            content_hasher.update(&content[start..start + length]);
+            start += length;
+        }
+        assert_eq!(content_hasher.finalize(), content_checksum);
+
+        let mut reordered_content = content[1..].to_vec();
+        reordered_content.push(content[0]);
+        assert_ne!(checksum(&reordered_content), content_checksum);
+    }
+
+    #[test]
+    fn test_chunks_for_range() {
+        let chunks_sizes = vec![10, 20, 5];
+        let cases = vec![
+            (0, 0, vec![]),
+            (10, 5, vec![]),
+            (0, 1, vec![(0, 0, 1)]),
+            (0, 10, vec![(0, 0, 10)]),
+            (0, 11, vec![(0, 0, 10), (1, 0, 1)]),
+            (9, 31, vec![(0, 9, 10), (1, 0, 20), (2, 0, 1)]),
+            (10, 30, vec![(1, 0, 20)]),
+            (32, 100, vec![(2, 2, 5)]),
+            (35, 100, vec![]),
+        ];
+
+        for (start, end, expected) in cases {
+            let chunks = chunks_for_range(&chunks_sizes, start, end);
+            assert_eq!(chunks, expected, "Failed for input: {:?}", (start, end));
+        }
+    }
+
+    #[test]
+    fn test_join_fs_path() {
+        let cases = vec![
+            ("/", "folder", "/folder"),
+            ("", "folder", "/folder"),
+            ("/test/path", "folder", "/test/path/folder"),
+            ("/test/path/", "folder", "/test/path/folder"),
+            ("test\\path", "folder", "/test/path/folder"),
+        ];
+
+        for (path, name, expected) in cases {
+            let joined_path = join_fs_path(path, name);
+            assert_eq!(
+                joined_path,
+                expected,
+                "Failed for input: {:?}",
+                (path, name)
+            );
+        }
+    }
+
     #[test]
     fn test_standardize_fs_path() {
         let cases = vec![
@@ -229,6 +964,7 @@ mod tests {
             ("\\", "/"),
             ("//", "/"),
             ("\\\\", "/"),
+            ("/cafe\u{301}/nai\u{308}ve", "/caf\u{e9}/na\u{ef}ve"),
         ];
 
         for (input, expected) in cases {
@@ -0,0 +1,59 @@
+//! This module provides garbage collection of file chunks that are no longer referenced by any file,
+//! such as the chunks left behind by a failed `create_file` call.
+
+use crate::files::*;
+use crate::uploads::get_uploading_chunks_hashes;
+use file_system_integrity::*;
+use hdk::prelude::*;
+
+/// A struct representing the report of a garbage collection in the File Storage zome.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct GarbageCollectionReport {
+    pub reclaimed_chunks: usize,
+    pub reclaimed_bytes: usize,
+    pub delete_actions: Vec<ActionHash>,
+}
+
+/// Deletes the file chunks authored by the calling agent that are not referenced by any live file metadata,
+/// nor by one of their upload sessions in progress, and reports how many chunks and bytes were reclaimed.
+#[hdk_extern]
+pub fn collect_garbage(_: ()) -> ExternResult<GarbageCollectionReport> {
+    let mut report = GarbageCollectionReport {
+        reclaimed_chunks: 0,
+        reclaimed_bytes: 0,
+        delete_actions: Vec::new(),
+    };
+
+    let uploading_chunks_hashes = get_uploading_chunks_hashes()?;
+
+    let chunks_records = query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::FileChunk.try_into()?)
+            .action_type(ActionType::Create)
+            .include_entries(true),
+    )?;
+
+    let mut visited_chunks_hashes: Vec<EntryHash> = Vec::new();
+    for chunk_record in chunks_records {
+        let chunk_hash = match chunk_record.action().entry_hash() {
+            Some(chunk_hash) => chunk_hash.clone(),
+            None => continue,
+        };
+        if visited_chunks_hashes.contains(&chunk_hash)
+            || uploading_chunks_hashes.contains(&chunk_hash)
+        {
+            continue;
+        }
+        visited_chunks_hashes.push(chunk_hash.clone());
+
+        let file_chunk = FileChunk::try_from(chunk_record)?;
+        let delete_actions = release_file_chunk(chunk_hash, &[])?;
+        if !delete_actions.is_empty() {
+            report.reclaimed_chunks += 1;
+            report.reclaimed_bytes += file_chunk.0.bytes().len();
+            report.delete_actions.extend(delete_actions);
+        }
+    }
+
+    Ok(report)
+}
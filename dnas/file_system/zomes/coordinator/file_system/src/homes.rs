@@ -0,0 +1,89 @@
+//! This module provides the per-agent home folders of the file system. When the `home_directories` DNA property is
+//! set, each agent stores their files and folders in `/<agent>`, and paths starting with `~` refer to the home folder
+//! of the calling agent.
+
+use crate::directories::{list_directory, DirectoryContent};
+use crate::files::{join_fs_path, standardize_fs_path};
+use file_system_integrity::*;
+use hdk::prelude::*;
+
+/// A struct representing the input for listing a folder in the home folder of an agent.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct ListHomeDirectoryInput {
+    pub agent: AgentPubKey,
+    pub path: String,
+}
+
+/// Returns the path of the home folder of the given agent.
+#[hdk_extern]
+pub fn get_home_directory(agent: AgentPubKey) -> ExternResult<String> {
+    Ok(home_directory_path(&agent))
+}
+
+/// Resolves a file system path as the zome does, expanding `~` to the home folder of the calling agent.
+#[hdk_extern]
+pub fn resolve_path(path: String) -> ExternResult<String> {
+    resolve_fs_path(&path)
+}
+
+/// Lists the files and folders directly within a folder of the home folder of the given agent.
+#[hdk_extern]
+pub fn list_home_directory(
+    list_home_directory_input: ListHomeDirectoryInput,
+) -> ExternResult<DirectoryContent> {
+    let home_directory = home_directory_path(&list_home_directory_input.agent);
+    let path = standardize_fs_path(&list_home_directory_input.path);
+
+    list_directory(join_fs_path(&home_directory, &path))
+}
+
+/// Returns a standardized filesystem path, where a leading `~` component is replaced by the home folder of the
+/// calling agent if home folders are enabled.
+pub fn resolve_fs_path(path: &str) -> ExternResult<String> {
+    if !file_system_properties()?.home_directories {
+        return Ok(standardize_fs_path(path));
+    }
+
+    let home_directory = home_directory_path(&agent_info()?.agent_initial_pubkey);
+    Ok(standardize_fs_path(&expand_home_directory(
+        path,
+        &home_directory,
+    )))
+}
+
+/// Replaces a leading `~` component of a path by the given home folder.
+fn expand_home_directory(path: &str, home_directory: &str) -> String {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            format!("{}{}", home_directory, rest)
+        }
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_home_directory() {
+        let cases = vec![
+            ("~", "/abc"),
+            ("~/", "/abc/"),
+            ("~/folder/file", "/abc/folder/file"),
+            ("~\\folder", "/abc\\folder"),
+            ("~folder", "~folder"),
+            ("/~/folder", "/~/folder"),
+            ("folder/~", "folder/~"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(
+                expand_home_directory(input, "/abc"),
+                expected,
+                "Failed for input: {:?}",
+                input
+            );
+        }
+    }
+}
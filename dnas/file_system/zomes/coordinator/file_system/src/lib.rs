@@ -1,9 +1,35 @@
+use encryption::*;
+use errors::*;
 use file_system_integrity::*;
 use files::*;
 use hdk::prelude::*;
-
+use homes::resolve_fs_path;
+use private_files::*;
+use sharing::{check_write_permission, get_readable_file_metadata};
+
+pub mod compression;
+pub mod directories;
+pub mod encryption;
+pub mod errors;
 pub mod files;
+pub mod garbage_collection;
+pub mod homes;
+pub mod private_files;
+pub mod quotas;
+pub mod sharing;
 pub mod signals;
+pub mod trash;
+pub mod uploads;
+
+/// Sets up the zome for the calling agent, allowing other agents to share files with them and publishing their
+/// encryption key.
+#[hdk_extern]
+pub fn init(_: ()) -> ExternResult<InitCallbackResult> {
+    sharing::create_receive_shared_file_grant()?;
+    create_my_encryption_key()?;
+
+    Ok(InitCallbackResult::Pass)
+}
 
 /// A struct representing the input for creating a new file in the File Storage zome.
 #[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
@@ -12,6 +38,15 @@ pub struct FileInput {
     pub path: String,
     pub file_type: String,
     pub content: SerializedBytes,
+    /// Agents for whom the content is encrypted, along with the author. The content is public if not set.
+    #[serde(default)]
+    pub encrypted_for: Option<Vec<AgentPubKey>>,
+    /// Codec to compress the chunks of the file with. The chunks are stored as is if not set.
+    #[serde(default)]
+    pub compression: Option<CompressionCodec>,
+    /// Whether the file is stored only on the source chain of the author, without being published to the DHT.
+    #[serde(default)]
+    pub private: bool,
 }
 
 /// A struct representing the output for creating or updating a file in the File Storage zome.
@@ -24,8 +59,9 @@ pub struct FileOutput {
 /// Creates a new file in the File Storage zome.
 #[hdk_extern]
 pub fn create_file(file_input: FileInput) -> ExternResult<FileOutput> {
-    let already_created_record =
-        get_file_metadata_by_path_and_name(file_input.path.clone(), file_input.name.clone());
+    let name = normalize_name(&file_input.name);
+    let path = resolve_fs_path(&file_input.path)?;
+    let already_created_record = get_file_metadata_by_path_and_name(path.clone(), name.clone());
 
     if already_created_record.is_ok() {
         return Err(wasm_error!(WasmErrorInner::Guest(String::from(
@@ -33,21 +69,45 @@ pub fn create_file(file_input: FileInput) -> ExternResult<FileOutput> {
         ))));
     }
 
-    let chunks_hashes = chunk_file(file_input.content.bytes().clone())?;
+    let (key_ref, encryption) = match &file_input.encrypted_for {
+        Some(agents) => {
+            let (key_ref, encryption) = create_file_encryption(agents)?;
+            (Some(key_ref), Some(encryption))
+        }
+        None => (None, None),
+    };
+    let encoding = ChunkEncoding::new(file_input.compression, key_ref)?;
+    let stored_chunks = chunk_file(
+        file_input.content.bytes().clone(),
+        &encoding,
+        file_input.private,
+    )?;
 
     let file_metadata = FileMetadata {
-        name: file_input.name.clone(),
+        name,
         author: agent_info()?.agent_initial_pubkey,
-        path: standardize_fs_path(&file_input.path),
+        path,
         created: sys_time()?,
         last_modified: sys_time()?,
         size: file_input.content.bytes().len(),
+        stored_size: stored_chunks.stored_size,
         file_type: file_input.file_type.clone(),
-        chunks_hashes: chunks_hashes.clone(),
+        chunks_hashes: stored_chunks.chunks_hashes.clone(),
+        chunks_sizes: stored_chunks.chunks_sizes,
+        content_sizes: stored_chunks.content_sizes,
+        checksum: stored_chunks.checksum,
+        content_checksum: stored_chunks.content_checksum,
+        encryption,
+        compression: file_input.compression,
     };
 
-    let metadata_record = create_file_metadata(file_metadata)?;
-    let chunks_records: Vec<Record> = chunks_hashes
+    let metadata_record = if file_input.private {
+        create_private_file_metadata(file_metadata)?
+    } else {
+        create_file_metadata(file_metadata)?
+    };
+    let chunks_records: Vec<Record> = stored_chunks
+        .chunks_hashes
         .iter()
         .map(|chunk_hash| get_file_chunk(chunk_hash.clone()).unwrap())
         .collect();
@@ -62,7 +122,7 @@ pub fn create_file(file_input: FileInput) -> ExternResult<FileOutput> {
 /// Retrieves a list of file chunk records associated with the specified file metadata entry hash.
 #[hdk_extern]
 pub fn get_file_chunks(file_metadata_hash: ActionHash) -> ExternResult<Vec<Record>> {
-    let record_option = get_file_metadata(file_metadata_hash)?;
+    let record_option = get_readable_file_metadata(file_metadata_hash)?;
     if record_option.is_none() {
         return Ok(Vec::new());
     }
@@ -75,43 +135,201 @@ pub fn get_file_chunks(file_metadata_hash: ActionHash) -> ExternResult<Vec<Recor
         return Ok(file_chunks);
     }
 
+    let encoding = ChunkEncoding::for_file(&file_metadata)?;
     for file_chunk_hash in file_metadata.chunks_hashes {
         let file_chunk = get_file_chunk(file_chunk_hash)?;
-        file_chunks.push(file_chunk);
+        file_chunks.push(encoding.decode_record(file_chunk)?);
     }
 
     Ok(file_chunks)
 }
 
-/// Retrieves the latest version of a file metadata entry for the specified hash.
+/// A struct representing the input for getting a single file chunk by its index in the File Storage zome.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct GetFileChunkByIndexInput {
+    pub original_file_metadata_hash: ActionHash,
+    pub index: usize,
+}
+
+/// A struct representing the output for getting a single file chunk by its index in the File Storage zome.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct FileChunkOutput {
+    pub file_chunk: Record,
+    pub chunks_count: usize,
+}
+
+/// Retrieves the chunk at the specified index of the latest version of a file, along with the total number of chunks.
 #[hdk_extern]
-pub fn get_file_metadata(original_file_metadata_hash: ActionHash) -> ExternResult<Option<Record>> {
-    let links = get_links(
-        original_file_metadata_hash.clone(),
-        LinkTypes::FileMetaDataUpdate,
-        None,
-    )?;
+pub fn get_file_chunk_by_index(
+    get_file_chunk_by_index_input: GetFileChunkByIndexInput,
+) -> ExternResult<FileChunkOutput> {
+    let file_metadata_record =
+        get_latest_file_metadata(get_file_chunk_by_index_input.original_file_metadata_hash)?
+            .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+                "Could not find the file metadata"
+            ))))?;
+    let file_metadata = FileMetadata::try_from(file_metadata_record)?;
+
+    let index = get_file_chunk_by_index_input.index;
+    let chunks_count = file_metadata.chunks_hashes.len();
+    let chunk_hash =
+        file_metadata
+            .chunks_hashes
+            .get(index)
+            .ok_or(FileSystemError::ChunkIndexOutOfRange {
+                index,
+                chunks_count,
+            })?;
+
+    let encoding = ChunkEncoding::for_file(&file_metadata)?;
+    Ok(FileChunkOutput {
+        file_chunk: encoding.decode_record(get_file_chunk(chunk_hash.clone())?)?,
+        chunks_count,
+    })
+}
+
+/// A struct representing the input for reading a byte range of a file in the File Storage zome.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct ReadFileRangeInput {
+    pub original_file_metadata_hash: ActionHash,
+    pub offset: usize,
+    pub length: usize,
+}
 
-    let latest_link = links
-        .into_iter()
-        .max_by(|link_a, link_b| link_a.timestamp.cmp(&link_b.timestamp));
+/// Reads a byte range of the latest version of a file, fetching only the file chunks overlapping the range.
+/// The range is truncated at the end of the file.
+#[hdk_extern]
+pub fn read_file_range(read_file_range_input: ReadFileRangeInput) -> ExternResult<SerializedBytes> {
+    let file_metadata_record = get_readable_file_metadata(
+        read_file_range_input.original_file_metadata_hash,
+    )?
+    .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+        "Could not find the file metadata"
+    ))))?;
+    let file_metadata = FileMetadata::try_from(file_metadata_record)?;
+
+    let start = read_file_range_input.offset;
+    let end = std::cmp::min(
+        start.saturating_add(read_file_range_input.length),
+        file_metadata.size,
+    );
+
+    let encoding = ChunkEncoding::for_file(&file_metadata)?;
+    let mut bytes = Vec::new();
+    if start >= end {
+        return Ok(SerializedBytes::from(UnsafeBytes::from(bytes)));
+    }
+
+    match file_metadata.content_chunks_sizes() {
+        Some(content_chunks_sizes) => {
+            for (index, from, to) in chunks_for_range(&content_chunks_sizes, start, end) {
+                let chunk_hash = file_metadata.chunks_hashes.get(index).ok_or(wasm_error!(
+                    WasmErrorInner::Guest(String::from("Chunk not found"))
+                ))?;
+                let file_chunk = FileChunk::try_from(get_file_chunk(chunk_hash.clone())?)?;
+                let chunk_bytes = encoding.decode(file_chunk.0.bytes())?;
+                if to > chunk_bytes.len() {
+                    return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+                        "Chunk is smaller than its declared size"
+                    ))));
+                }
+                bytes.extend_from_slice(&chunk_bytes[from..to]);
+            }
+        }
+        // The content sizes of encrypted compressed files are not published, so their chunks are read in order
+        None => {
+            let mut chunk_start = 0;
+            for chunk_hash in &file_metadata.chunks_hashes {
+                if chunk_start >= end {
+                    break;
+                }
+                let file_chunk = FileChunk::try_from(get_file_chunk(chunk_hash.clone())?)?;
+                let chunk_bytes = encoding.decode(file_chunk.0.bytes())?;
+                let chunk_end = chunk_start + chunk_bytes.len();
+                if chunk_end > start {
+                    let from = start.saturating_sub(chunk_start);
+                    let to = std::cmp::min(end, chunk_end) - chunk_start;
+                    bytes.extend_from_slice(&chunk_bytes[from..to]);
+                }
+                chunk_start = chunk_end;
+            }
+        }
+    }
+
+    Ok(SerializedBytes::from(UnsafeBytes::from(bytes)))
+}
 
-    let latest_file_metadata_hash = match latest_link {
-        Some(link) => ActionHash::from(link.target.clone()),
-        None => original_file_metadata_hash.clone(),
+/// A struct representing the result of the verification of a file in the File Storage zome.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct FileVerification {
+    pub checksum: String,
+    pub computed_checksum: String,
+    pub content_checksum: Option<String>,
+    pub computed_content_checksum: Option<String>,
+    pub valid: bool,
+}
+
+/// Re-fetches the chunks of the latest version of a file and checks that the stored chunks, in order, match its
+/// checksum, and that their decoded content matches its content checksum if the file has one. Encrypted files have
+/// no content checksum, so the agents they are encrypted for get the checksum of their decrypted content to compare
+/// it with their own.
+#[hdk_extern]
+pub fn verify_file(original_file_metadata_hash: ActionHash) -> ExternResult<FileVerification> {
+    let file_metadata_record =
+        get_latest_file_metadata(original_file_metadata_hash)?.ok_or(wasm_error!(
+            WasmErrorInner::Guest(String::from("Could not find the file metadata"))
+        ))?;
+    let file_metadata = FileMetadata::try_from(file_metadata_record)?;
+
+    let encoding = match can_open_file_key(&file_metadata)? {
+        true => Some(ChunkEncoding::for_file(&file_metadata)?),
+        false => None,
     };
+    let mut stored_hasher = ContentHasher::default();
+    let mut content_hasher = ContentHasher::default();
+    for chunk_hash in file_metadata.chunks_hashes {
+        let file_chunk = FileChunk::try_from(get_file_chunk(chunk_hash)?)?;
+        stored_hasher.update(file_chunk.0.bytes());
+        if let Some(encoding) = &encoding {
+            content_hasher.update(&encoding.decode(file_chunk.0.bytes())?);
+        }
+    }
+    let computed_checksum = stored_hasher.finalize();
+    let computed_content_checksum = encoding.map(|_| content_hasher.finalize());
+
+    Ok(FileVerification {
+        valid: computed_checksum == file_metadata.checksum
+            && match (&file_metadata.content_checksum, &computed_content_checksum) {
+                (Some(content_checksum), Some(computed_content_checksum)) => {
+                    content_checksum == computed_content_checksum
+                }
+                _ => true,
+            },
+        checksum: file_metadata.checksum,
+        computed_checksum,
+        content_checksum: file_metadata.content_checksum,
+        computed_content_checksum,
+    })
+}
 
-    get(latest_file_metadata_hash, GetOptions::default())
+/// Retrieves the latest version of a file metadata entry for the specified hash, from the DHT or from the private
+/// files of the calling agent. Other agents can only retrieve the files shared with them.
+#[hdk_extern]
+pub fn get_file_metadata(original_file_metadata_hash: ActionHash) -> ExternResult<Option<Record>> {
+    get_readable_file_metadata(original_file_metadata_hash)
 }
 
-/// Retrieves all file metadata entries recursively from the specified directory path.
+/// Retrieves all file metadata entries recursively from the specified directory path, including the private files
+/// of the calling agent.
 #[hdk_extern]
 pub fn get_files_metadata_by_path_recursively(path_string: String) -> ExternResult<Vec<Record>> {
-    let path_string = fs_path_to_dht_path(path_string.as_str());
-    warn!("path_string: {:?}", path_string);
-    let path = Path::from(path_string);
+    let fs_path = resolve_fs_path(&path_string)?;
+    let path = fs_path_to_dht_path(&fs_path, &file_system_properties()?);
 
-    get_files_metadata_recursively(path)
+    let mut files = get_files_metadata_recursively(path)?;
+    files.extend(get_private_files_metadata_by_path(&fs_path, true)?);
+
+    Ok(files)
 }
 
 /// A struct representing the input for updating a file's metadata in the File Storage zome.
@@ -129,44 +347,42 @@ pub fn update_file(
     let original_file_metadata_hash = update_file_metadata_input.original_file_metadata_hash;
     let new_content = update_file_metadata_input.new_content.bytes();
 
-    let all_update_links = get_links(
-        original_file_metadata_hash.clone(),
-        LinkTypes::FileMetaDataUpdate,
-        None,
-    )?;
-    let latest_link = all_update_links
-        .into_iter()
-        .max_by(|link_a, link_b| link_a.timestamp.cmp(&link_b.timestamp));
+    check_write_permission(&original_file_metadata_hash)?;
 
-    let previous_file_metadata_hash = match latest_link {
-        Some(link) => Some(ActionHash::from(link.target.clone())),
-        None => None,
-    };
+    let previous_file_metadata_hash =
+        get_latest_file_metadata_update_hash(original_file_metadata_hash.clone())?;
 
-    let chunks_hashes = chunk_file(new_content.to_vec())?;
-    let now = sys_time()?;
-
-    let file_metadata_record =
-        get_file_metadata(original_file_metadata_hash.clone())?.ok_or(wasm_error!(
-            WasmErrorInner::Guest(String::from("Could not find the file metadata"))
-        ))?;
+    let file_metadata_record = get_latest_file_metadata(original_file_metadata_hash.clone())?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Could not find the file metadata"
+        ))))?;
     let mut file_metadata = FileMetadata::try_from(file_metadata_record.clone())?;
-    let old_chunks_hashes = file_metadata.chunks_hashes.clone();
+    let private = is_private_record(&file_metadata_record);
+
+    let encoding = ChunkEncoding::for_file(&file_metadata)?;
+    let stored_chunks = chunk_file(new_content.to_vec(), &encoding, private)?;
+    let chunks_hashes = stored_chunks.chunks_hashes;
+    let now = sys_time()?;
 
     file_metadata.last_modified = now;
     file_metadata.size = new_content.len();
+    file_metadata.stored_size = stored_chunks.stored_size;
     file_metadata.chunks_hashes = chunks_hashes.clone();
-
-    for chunk_hash in old_chunks_hashes {
-        let chunk_record = get_file_chunk(chunk_hash.clone())?;
-        delete_entry(chunk_record.signed_action.hashed.hash)?;
-    }
-
-    let updated_metadata_record = update_file_metadata(
-        original_file_metadata_hash.clone(),
-        previous_file_metadata_hash.clone(),
-        file_metadata,
-    )?;
+    file_metadata.chunks_sizes = stored_chunks.chunks_sizes;
+    file_metadata.content_sizes = stored_chunks.content_sizes;
+    file_metadata.checksum = stored_chunks.checksum;
+    file_metadata.content_checksum = stored_chunks.content_checksum;
+
+    // Private files are not linked to their versions, so the latest version is updated directly
+    let updated_metadata_record = if private {
+        update_private_file_metadata(file_metadata_record.action_address().clone(), file_metadata)?
+    } else {
+        update_file_metadata(
+            original_file_metadata_hash.clone(),
+            previous_file_metadata_hash.clone(),
+            file_metadata,
+        )?
+    };
 
     let chunks_records: Vec<Record> = chunks_hashes
         .iter()
@@ -180,38 +396,222 @@ pub fn update_file(
     Ok(records)
 }
 
-/// Deletes a file and all its versions, including the file chunks.
+/// Retrieves every version of a file metadata entry, from the original to the latest, ordered by timestamp.
 #[hdk_extern]
-pub fn delete_file(original_file_metadata_hash: ActionHash) -> ExternResult<Vec<ActionHash>> {
-    let mut delete_actions: Vec<ActionHash> = Vec::new();
-    let mut update_links = get_links(
+pub fn get_file_versions(original_file_metadata_hash: ActionHash) -> ExternResult<Vec<Record>> {
+    let versions = get_file_metadata_versions(original_file_metadata_hash.clone())?;
+    if versions.first().map_or(true, is_private_record) {
+        return get_private_file_versions(&original_file_metadata_hash);
+    }
+
+    Ok(versions)
+}
+
+/// A struct representing the input for restoring a previous version of a file in the File Storage zome.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct RestoreFileVersionInput {
+    pub original_file_metadata_hash: ActionHash,
+    pub version_file_metadata_hash: ActionHash,
+}
+
+/// Restores the content of a previous version of a file by creating a new version that reuses its file chunks.
+/// The current name and path of the file are kept.
+#[hdk_extern]
+pub fn restore_file_version(
+    restore_file_version_input: RestoreFileVersionInput,
+) -> ExternResult<Record> {
+    let original_file_metadata_hash = restore_file_version_input.original_file_metadata_hash;
+    let version_file_metadata_hash = restore_file_version_input.version_file_metadata_hash;
+
+    let versions = get_file_metadata_versions(original_file_metadata_hash.clone())?;
+    let version_record = versions
+        .iter()
+        .find(|record| record.signed_action.hashed.hash == version_file_metadata_hash)
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Version not found for this file"
+        ))))?;
+    let version_file_metadata = FileMetadata::try_from(version_record.clone())?;
+
+    let file_metadata_record = get_latest_file_metadata(original_file_metadata_hash.clone())?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Could not find the file metadata"
+        ))))?;
+    check_not_private(&file_metadata_record)?;
+    let mut file_metadata = FileMetadata::try_from(file_metadata_record)?;
+
+    let previous_file_metadata_hash =
+        get_latest_file_metadata_update_hash(original_file_metadata_hash.clone())?;
+
+    file_metadata.last_modified = sys_time()?;
+    file_metadata.size = version_file_metadata.size;
+    file_metadata.stored_size = version_file_metadata.stored_size;
+    file_metadata.file_type = version_file_metadata.file_type;
+    file_metadata.chunks_hashes = version_file_metadata.chunks_hashes;
+    file_metadata.chunks_sizes = version_file_metadata.chunks_sizes;
+    file_metadata.content_sizes = version_file_metadata.content_sizes;
+    file_metadata.checksum = version_file_metadata.checksum;
+    file_metadata.content_checksum = version_file_metadata.content_checksum;
+
+    update_file_metadata(
+        original_file_metadata_hash,
+        previous_file_metadata_hash,
+        file_metadata,
+    )
+}
+
+/// A struct representing the input for moving a file in the File Storage zome.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct MoveFileInput {
+    pub original_file_metadata_hash: ActionHash,
+    pub new_path: Option<String>,
+    pub new_name: Option<String>,
+}
+
+/// Moves and/or renames a file by creating a new version of its metadata and relinking it under the destination path.
+#[hdk_extern]
+pub fn move_file(move_file_input: MoveFileInput) -> ExternResult<Record> {
+    let original_file_metadata_hash = move_file_input.original_file_metadata_hash;
+
+    let file_metadata_record = get_latest_file_metadata(original_file_metadata_hash.clone())?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Could not find the file metadata"
+        ))))?;
+    check_not_private(&file_metadata_record)?;
+    let mut file_metadata = FileMetadata::try_from(file_metadata_record.clone())?;
+
+    let old_path = file_metadata.path.clone();
+    let new_path = match move_file_input.new_path {
+        Some(new_path) => resolve_fs_path(&new_path)?,
+        None => old_path.clone(),
+    };
+    let new_name = match move_file_input.new_name {
+        Some(new_name) => normalize_name(&new_name),
+        None => file_metadata.name.clone(),
+    };
+
+    if new_path == old_path && new_name == file_metadata.name {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "File is already at the destination"
+        ))));
+    }
+
+    if get_file_metadata_by_path_and_name(new_path.clone(), new_name.clone()).is_ok() {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "A file already exists at the destination"
+        ))));
+    }
+
+    let previous_file_metadata_hash =
+        get_latest_file_metadata_update_hash(original_file_metadata_hash.clone())?;
+
+    file_metadata.path = new_path.clone();
+    file_metadata.name = new_name;
+    file_metadata.last_modified = sys_time()?;
+
+    let updated_metadata_record = update_file_metadata(
         original_file_metadata_hash.clone(),
-        LinkTypes::FileMetaDataUpdate,
-        None,
+        previous_file_metadata_hash,
+        file_metadata,
     )?;
 
-    let binding = update_links.clone();
-    let latest_link = binding
-        .iter()
-        .max_by(|link_a, link_b| link_a.timestamp.cmp(&link_b.timestamp));
+    if new_path != old_path {
+        move_file_metadata_link(
+            original_file_metadata_hash,
+            updated_metadata_record.action_address().clone(),
+            &old_path,
+            &new_path,
+        )?;
+    }
 
-    if let Some(latest_link) = latest_link {
-        let latest_metadata_hash = ActionHash::from(latest_link.clone().target);
+    Ok(updated_metadata_record)
+}
 
-        update_links.retain(|link| link.target != latest_metadata_hash.clone().into());
-        for link in update_links {
-            delete_actions.push(delete_entry(ActionHash::from(link.target))?);
-        }
+/// A struct representing the input for copying a file in the File Storage zome.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct CopyFileInput {
+    pub original_file_metadata_hash: ActionHash,
+    pub new_path: String,
+    pub new_name: Option<String>,
+}
+
+/// Copies a file by creating a new file metadata entry at the destination that reuses the existing file chunks.
+#[hdk_extern]
+pub fn copy_file(copy_file_input: CopyFileInput) -> ExternResult<Record> {
+    let file_metadata_record = get_latest_file_metadata(
+        copy_file_input.original_file_metadata_hash,
+    )?
+    .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+        "Could not find the file metadata"
+    ))))?;
+    check_not_private(&file_metadata_record)?;
+    let source_file_metadata = FileMetadata::try_from(file_metadata_record)?;
+
+    let new_path = resolve_fs_path(&copy_file_input.new_path)?;
+    let new_name = match copy_file_input.new_name {
+        Some(new_name) => normalize_name(&new_name),
+        None => source_file_metadata.name.clone(),
+    };
+
+    if get_file_metadata_by_path_and_name(new_path.clone(), new_name.clone()).is_ok() {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "A file already exists at the destination"
+        ))));
+    }
 
-        let file_chunks = get_file_chunks(latest_metadata_hash.clone())?;
-        for file_chunk in file_chunks {
-            delete_actions.push(delete_entry(file_chunk.signed_action.hashed.hash)?);
+    let now = sys_time()?;
+    let file_metadata = FileMetadata {
+        name: new_name,
+        author: agent_info()?.agent_initial_pubkey,
+        path: new_path,
+        created: now,
+        last_modified: now,
+        size: source_file_metadata.size,
+        stored_size: source_file_metadata.stored_size,
+        file_type: source_file_metadata.file_type,
+        chunks_hashes: source_file_metadata.chunks_hashes,
+        chunks_sizes: source_file_metadata.chunks_sizes,
+        content_sizes: source_file_metadata.content_sizes,
+        checksum: source_file_metadata.checksum,
+        content_checksum: source_file_metadata.content_checksum,
+        encryption: source_file_metadata.encryption,
+        compression: source_file_metadata.compression,
+    };
+
+    create_file_metadata(file_metadata)
+}
+
+/// Deletes a file and all its versions, including the file chunks of every version
+/// that are not referenced by any other file. The chunks of a private file are kept on the source chain.
+#[hdk_extern]
+pub fn delete_file(original_file_metadata_hash: ActionHash) -> ExternResult<Vec<ActionHash>> {
+    let mut delete_actions: Vec<ActionHash> = Vec::new();
+
+    let versions = get_file_metadata_versions(original_file_metadata_hash.clone())?;
+    if versions.first().map_or(true, is_private_record) {
+        return delete_private_file(&original_file_metadata_hash);
+    }
+
+    sharing::revoke_all_shares(&original_file_metadata_hash)?;
+
+    let mut chunks_hashes: Vec<EntryHash> = Vec::new();
+    let mut versions_hashes: Vec<ActionHash> = Vec::new();
+    for version in versions.iter() {
+        let file_metadata = FileMetadata::try_from(version.clone())?;
+        for chunk_hash in file_metadata.chunks_hashes {
+            if !chunks_hashes.contains(&chunk_hash) {
+                chunks_hashes.push(chunk_hash);
+            }
         }
+        versions_hashes.push(version.signed_action.hashed.hash.clone());
+    }
 
-        delete_actions.push(delete_entry(latest_metadata_hash)?);
+    for chunk_hash in chunks_hashes {
+        delete_actions.extend(release_file_chunk(chunk_hash, &versions_hashes)?);
     }
 
-    delete_actions.push(delete_entry(original_file_metadata_hash)?);
+    for version_hash in versions_hashes.into_iter().rev() {
+        delete_actions.push(delete_entry(version_hash)?);
+    }
 
     Ok(delete_actions)
 }
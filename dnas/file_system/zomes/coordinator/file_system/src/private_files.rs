@@ -0,0 +1,260 @@
+//! This module provides private files, stored only on the source chain of their author. Their metadata and chunks
+//! are private entries that are never published to the DHT, so they are not linked from the file system tree and
+//! are found by querying the source chain of the calling agent instead.
+
+use crate::files::get_deleted_actions_hashes;
+use file_system_integrity::*;
+use hdk::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Returns whether a file metadata record is a private entry.
+pub fn is_private_record(record: &Record) -> bool {
+    matches!(
+        record.action().entry_type(),
+        Some(EntryType::App(AppEntryDef {
+            visibility: EntryVisibility::Private,
+            ..
+        }))
+    )
+}
+
+/// Fails if a file is private, for the operations that would publish it to the DHT.
+pub fn check_not_private(file_metadata_record: &Record) -> ExternResult<()> {
+    if is_private_record(file_metadata_record) {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Operation not supported on private files"
+        ))));
+    }
+
+    Ok(())
+}
+
+/// Creates a private file chunk on the source chain of the calling agent, unless it is already there.
+pub fn create_private_file_chunk(file_chunk: FileChunk) -> ExternResult<Record> {
+    let file_chunk_hash = hash_entry(&file_chunk)?;
+
+    if let Some(record) = get_private_file_chunk(file_chunk_hash.clone())? {
+        return Ok(record);
+    }
+    create_entry(&EntryTypes::PrivateFileChunk(file_chunk))?;
+
+    get_private_file_chunk(file_chunk_hash)?.ok_or(wasm_error!(WasmErrorInner::Guest(
+        String::from("Could not find the newly created file chunk")
+    )))
+}
+
+/// Retrieves a private file chunk by its hash from the source chain of the calling agent.
+pub fn get_private_file_chunk(file_chunk_hash: EntryHash) -> ExternResult<Option<Record>> {
+    Ok(query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::PrivateFileChunk.try_into()?)
+            .entry_hashes(HashSet::from([file_chunk_hash]))
+            .include_entries(true),
+    )?
+    .pop())
+}
+
+/// Creates a private file metadata entry on the source chain of the calling agent. Private files do not count
+/// towards the storage quotas, which only limit what an agent publishes to the DHT.
+pub fn create_private_file_metadata(file_metadata: FileMetadata) -> ExternResult<Record> {
+    let action_hash = create_entry(&EntryTypes::PrivateFileMetadata(file_metadata))?;
+
+    get_private_file_metadata(&action_hash)?.ok_or(wasm_error!(WasmErrorInner::Guest(
+        String::from("Could not find the newly created file metadata")
+    )))
+}
+
+/// Updates a private file by creating a new version of its metadata from the given version.
+pub fn update_private_file_metadata(
+    previous_file_metadata_hash: ActionHash,
+    file_metadata: FileMetadata,
+) -> ExternResult<Record> {
+    let updated_metadata_hash = update_entry(
+        previous_file_metadata_hash,
+        &EntryTypes::PrivateFileMetadata(file_metadata),
+    )?;
+
+    get_private_file_metadata(&updated_metadata_hash)?.ok_or(wasm_error!(WasmErrorInner::Guest(
+        String::from("Could not find the file metadata")
+    )))
+}
+
+/// Deletes every version of a private file of the calling agent. Its chunks are kept, since entries cannot be removed
+/// from the source chain anyway.
+pub fn delete_private_file(file_metadata_hash: &ActionHash) -> ExternResult<Vec<ActionHash>> {
+    let versions = get_private_file_versions(file_metadata_hash)?;
+    if versions.is_empty() {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Could not find the file metadata"
+        ))));
+    }
+
+    let mut delete_actions = Vec::new();
+    for version in versions.into_iter().rev() {
+        delete_actions.push(delete_entry(version.action_address().clone())?);
+    }
+
+    Ok(delete_actions)
+}
+
+/// Retrieves the latest version of a private file of the calling agent from the hash of any of its versions.
+pub fn get_private_file_metadata(file_metadata_hash: &ActionHash) -> ExternResult<Option<Record>> {
+    Ok(get_private_file_versions(file_metadata_hash)?.pop())
+}
+
+/// Retrieves every version of a private file of the calling agent from the hash of any of its versions, from the
+/// original to the latest.
+pub fn get_private_file_versions(file_metadata_hash: &ActionHash) -> ExternResult<Vec<Record>> {
+    Ok(get_private_files_versions()?
+        .into_iter()
+        .find(|versions| {
+            versions
+                .iter()
+                .any(|record| record.action_address() == file_metadata_hash)
+        })
+        .unwrap_or_default())
+}
+
+/// Retrieves the latest version of the private files of the calling agent directly within a directory path, or at
+/// any depth below it if recursive.
+pub fn get_private_files_metadata_by_path(
+    path: &str,
+    recursive: bool,
+) -> ExternResult<Vec<Record>> {
+    let properties = file_system_properties()?;
+    let mut files = Vec::new();
+
+    for mut versions in get_private_files_versions()? {
+        let record = match versions.pop() {
+            Some(record) => record,
+            None => continue,
+        };
+        let file_metadata = FileMetadata::try_from(record.clone())?;
+        match sub_path_components(&file_metadata.path, path, &properties) {
+            Some(components) if recursive || components.is_empty() => files.push(record),
+            _ => (),
+        }
+    }
+
+    Ok(files)
+}
+
+/// Returns the names of the folders directly within a directory path that hold private files of the calling agent.
+pub fn get_private_directories_names(path: &str) -> ExternResult<Vec<String>> {
+    let properties = file_system_properties()?;
+    let mut names: Vec<String> = Vec::new();
+
+    for record in get_private_files_metadata_by_path(path, true)? {
+        let file_metadata = FileMetadata::try_from(record)?;
+        let name = match sub_path_components(&file_metadata.path, path, &properties) {
+            Some(components) if !components.is_empty() => components[0].to_string(),
+            _ => continue,
+        };
+        if !names
+            .iter()
+            .any(|other| properties.name_key(other) == properties.name_key(&name))
+        {
+            names.push(name);
+        }
+    }
+
+    Ok(names)
+}
+
+/// Retrieves the versions of every live private file of the calling agent, from the original to the latest, in the
+/// order the files were created. Each update of a private file updates its previous version.
+fn get_private_files_versions() -> ExternResult<Vec<Vec<Record>>> {
+    let deleted_actions_hashes = get_deleted_actions_hashes()?;
+    let file_metadata_records = query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::PrivateFileMetadata.try_into()?)
+            .include_entries(true),
+    )?;
+
+    let mut files_versions: Vec<Vec<Record>> = Vec::new();
+    let mut file_indexes: HashMap<ActionHash, usize> = HashMap::new();
+    for record in file_metadata_records {
+        let index = match record.action() {
+            Action::Update(update) => match file_indexes.get(&update.original_action_address) {
+                Some(index) => *index,
+                None => continue,
+            },
+            _ => {
+                files_versions.push(Vec::new());
+                files_versions.len() - 1
+            }
+        };
+        file_indexes.insert(record.action_address().clone(), index);
+        files_versions[index].push(record);
+    }
+
+    Ok(files_versions
+        .into_iter()
+        .filter(|versions| {
+            versions.last().map_or(false, |record| {
+                !deleted_actions_hashes.contains(record.action_address())
+            })
+        })
+        .collect())
+}
+
+/// Returns the components of a standardized path below a standardized directory path, comparing components by
+/// their name key, or `None` if the path is not within the directory.
+fn sub_path_components<'a>(
+    path: &'a str,
+    directory_path: &str,
+    properties: &FileSystemProperties,
+) -> Option<Vec<&'a str>> {
+    let mut components = path.split('/').filter(|component| !component.is_empty());
+
+    for directory_component in directory_path
+        .split('/')
+        .filter(|component| !component.is_empty())
+    {
+        match components.next() {
+            Some(component)
+                if properties.name_key(component) == properties.name_key(directory_component) => {}
+            _ => return None,
+        }
+    }
+
+    Some(components.collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sub_path_components() {
+        let properties = FileSystemProperties::default();
+        let cases = vec![
+            ("/", "/", Some(vec![])),
+            ("/a/b", "/", Some(vec!["a", "b"])),
+            ("/a/b", "/a", Some(vec!["b"])),
+            ("/a/b", "/a/b", Some(vec![])),
+            ("/a/b", "/a/b/c", None),
+            ("/a/b", "/b", None),
+            ("/ab", "/a", None),
+            ("/A/b", "/a", None),
+        ];
+
+        for (path, directory_path, expected) in cases {
+            assert_eq!(
+                sub_path_components(path, directory_path, &properties),
+                expected,
+                "Failed for input: {:?}",
+                (path, directory_path)
+            );
+        }
+
+        let properties = FileSystemProperties {
+            case_insensitive: true,
+            ..FileSystemProperties::default()
+        };
+        assert_eq!(
+            sub_path_components("/A/b", "/a", &properties),
+            Some(vec!["b"])
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! This module provides the storage accounting of each agent, computed from the file metadata on their
+//! source chain, and enforces the storage quotas set in the DNA properties.
+
+use crate::errors::*;
+use crate::files::{get_deleted_actions_hashes, get_file_chunk};
+use crate::uploads::get_uploading_chunks_hashes;
+use file_system_integrity::*;
+use hdk::prelude::*;
+use std::collections::hash_map::Entry as MapEntry;
+use std::collections::HashMap;
+
+/// A struct representing the storage used by an agent in the File Storage zome, with the quotas that apply to it.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct StorageUsage {
+    pub files: usize,
+    pub bytes: usize,
+    pub max_files: Option<usize>,
+    pub max_bytes: Option<usize>,
+}
+
+/// Returns the number of files and the number of bytes stored by the calling agent.
+/// Bytes are counted once per distinct chunk across all versions of the agent's files and their unfinished uploads.
+#[hdk_extern]
+pub fn get_my_usage(_: ()) -> ExternResult<StorageUsage> {
+    let properties = file_system_properties()?;
+    let (files, chunks_sizes) = get_live_files_chunks_sizes()?;
+
+    Ok(StorageUsage {
+        files,
+        bytes: chunks_sizes.values().sum(),
+        max_files: properties.max_files_per_agent,
+        max_bytes: properties.max_bytes_per_agent,
+    })
+}
+
+/// Checks that storing a new version of a file, or a new file, keeps the calling agent within their quotas.
+pub fn check_storage_quota(file_metadata: &FileMetadata, new_file: bool) -> ExternResult<()> {
+    let properties = file_system_properties()?;
+    if properties.max_files_per_agent.is_none() && properties.max_bytes_per_agent.is_none() {
+        return Ok(());
+    }
+
+    let (mut files, mut chunks_sizes) = get_live_files_chunks_sizes()?;
+    if new_file {
+        files += 1;
+    }
+    for (chunk_hash, chunk_size) in file_metadata
+        .chunks_hashes
+        .iter()
+        .zip(file_metadata.chunks_sizes.iter())
+    {
+        chunks_sizes.insert(chunk_hash.clone(), *chunk_size);
+    }
+    let bytes = chunks_sizes.values().sum();
+
+    if let Some(max_files) = properties.max_files_per_agent {
+        if files > max_files {
+            return Err(FileSystemError::FilesQuotaExceeded { max_files }.into());
+        }
+    }
+
+    if let Some(max_bytes) = properties.max_bytes_per_agent {
+        if bytes > max_bytes {
+            return Err(FileSystemError::BytesQuotaExceeded { bytes, max_bytes }.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that storing a chunk of an upload session keeps the calling agent within their bytes quota.
+pub fn check_upload_quota(file_chunk: &FileChunk) -> ExternResult<()> {
+    let max_bytes = match file_system_properties()?.max_bytes_per_agent {
+        Some(max_bytes) => max_bytes,
+        None => return Ok(()),
+    };
+
+    let (_, mut chunks_sizes) = get_live_files_chunks_sizes()?;
+    chunks_sizes.insert(hash_entry(file_chunk)?, file_chunk.0.bytes().len());
+    let bytes = chunks_sizes.values().sum();
+
+    if bytes > max_bytes {
+        return Err(FileSystemError::BytesQuotaExceeded { bytes, max_bytes }.into());
+    }
+
+    Ok(())
+}
+
+/// Returns the number of live files of the calling agent, and the size of each distinct chunk referenced by
+/// a live version of these files or uploaded to an unfinished upload session, read from their source chain.
+fn get_live_files_chunks_sizes() -> ExternResult<(usize, HashMap<EntryHash, usize>)> {
+    let deleted_actions_hashes = get_deleted_actions_hashes()?;
+
+    let file_metadata_records = query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::FileMetadata.try_into()?)
+            .include_entries(true),
+    )?;
+
+    let mut files = 0;
+    let mut chunks_sizes = HashMap::new();
+    for record in file_metadata_records {
+        if deleted_actions_hashes.contains(record.action_address()) {
+            continue;
+        }
+        if let Action::Create(_) = record.action() {
+            files += 1;
+        }
+
+        let file_metadata = FileMetadata::try_from(record)?;
+        for (chunk_hash, chunk_size) in file_metadata
+            .chunks_hashes
+            .into_iter()
+            .zip(file_metadata.chunks_sizes)
+        {
+            chunks_sizes.insert(chunk_hash, chunk_size);
+        }
+    }
+
+    for chunk_hash in get_uploading_chunks_hashes()? {
+        if let MapEntry::Vacant(entry) = chunks_sizes.entry(chunk_hash) {
+            let file_chunk = FileChunk::try_from(get_file_chunk(entry.key().clone())?)?;
+            entry.insert(file_chunk.0.bytes().len());
+        }
+    }
+
+    Ok((files, chunks_sizes))
+}
@@ -0,0 +1,433 @@
+//! This module provides the sharing of files with other agents. Sharing a file creates a capability grant assigned
+//! to the agent for the functions matching the given permission, sends them the secret of the grant, and links the
+//! file from the agent so they can list the files shared with them.
+
+use crate::files::get_latest_file_metadata;
+use crate::private_files::check_not_private;
+use crate::{FileOutput, UpdateFileMetadataInput};
+use file_system_integrity::*;
+use hdk::prelude::*;
+use std::collections::BTreeSet;
+
+/// Tag of the capability grants and claims of shared files.
+const SHARED_FILE_CAP_TAG: &str = "shared_file";
+
+/// Tag of the capability grant allowing the authors of files to send their shares to the calling agent.
+const RECEIVE_SHARED_FILE_CAP_TAG: &str = "receive_shared_file";
+
+/// Functions of the author's cell that an agent a file is shared with can call.
+const READ_FUNCTIONS: [&str; 3] = ["get_file_metadata", "get_file_chunks", "read_file_range"];
+
+/// Functions of the author's cell that an agent a file is shared with for writing can also call.
+const WRITE_FUNCTIONS: [&str; 1] = ["update_file"];
+
+/// A struct representing the input for sharing a file with an agent in the File Storage zome.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct ShareFileInput {
+    pub original_file_metadata_hash: ActionHash,
+    pub agent: AgentPubKey,
+    pub permission: SharePermission,
+}
+
+/// A struct representing the input for revoking the share of a file with an agent in the File Storage zome.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct UnshareFileInput {
+    pub original_file_metadata_hash: ActionHash,
+    pub agent: AgentPubKey,
+}
+
+/// A struct representing the secret of a capability grant sent to the agent a file is shared with.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct ReceiveSharedFileInput {
+    pub original_file_metadata_hash: ActionHash,
+    pub secret: CapSecret,
+}
+
+/// A struct representing a file shared with the calling agent, with its latest metadata.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct SharedFile {
+    pub owner: AgentPubKey,
+    pub permission: SharePermission,
+    pub file_metadata: Record,
+}
+
+/// A struct representing the input for reading a file shared with the calling agent.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct GetSharedFileInput {
+    pub owner: AgentPubKey,
+    pub original_file_metadata_hash: ActionHash,
+}
+
+/// A struct representing the input for updating a file shared with the calling agent for writing.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct UpdateSharedFileInput {
+    pub owner: AgentPubKey,
+    pub original_file_metadata_hash: ActionHash,
+    pub new_content: SerializedBytes,
+}
+
+/// Shares a file of the calling agent with another agent, replacing any previous share of the file with them.
+/// Returns the hash of the shared-with-me link.
+#[hdk_extern]
+pub fn share_file(share_file_input: ShareFileInput) -> ExternResult<ActionHash> {
+    let original_file_metadata_hash = share_file_input.original_file_metadata_hash;
+    let agent = share_file_input.agent;
+    let my_pub_key = agent_info()?.agent_initial_pubkey;
+
+    let file_metadata_record = get_latest_file_metadata(original_file_metadata_hash.clone())?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Could not find the file metadata"
+        ))))?;
+    check_not_private(&file_metadata_record)?;
+    if file_metadata_record.action().author() != &my_pub_key {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Only the author of a file can share it"
+        ))));
+    }
+    if agent == my_pub_key {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Cannot share a file with its author"
+        ))));
+    }
+
+    revoke_shares(&original_file_metadata_hash, &agent)?;
+
+    let zome_name = zome_info()?.name;
+    let mut function_names = READ_FUNCTIONS.to_vec();
+    if share_file_input.permission == SharePermission::Write {
+        function_names.extend(WRITE_FUNCTIONS);
+    }
+    let functions = function_names
+        .into_iter()
+        .map(|function_name| (zome_name.clone(), FunctionName::from(function_name)))
+        .collect();
+
+    let secret = generate_cap_secret()?;
+    let cap_grant_hash = create_cap_grant(CapGrantEntry {
+        tag: String::from(SHARED_FILE_CAP_TAG),
+        access: CapAccess::Assigned {
+            secret,
+            assignees: BTreeSet::from([agent.clone()]),
+        },
+        functions: GrantedFunctions::Listed(functions),
+    })?;
+
+    let response = call_remote(
+        agent.clone(),
+        zome_name,
+        FunctionName::from("receive_shared_file"),
+        None,
+        ReceiveSharedFileInput {
+            original_file_metadata_hash: original_file_metadata_hash.clone(),
+            secret,
+        },
+    )?;
+    if !matches!(response, ZomeCallResponse::Ok(_)) {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Could not send the file share to the agent: {:?}",
+            response
+        ))));
+    }
+
+    create_link(
+        agent,
+        original_file_metadata_hash,
+        LinkTypes::SharedWithMe,
+        shared_file_tag(SharedFileTag {
+            permission: share_file_input.permission,
+            cap_grant_hash,
+        })?,
+    )
+}
+
+/// Revokes the share of a file of the calling agent with another agent, deleting its capability grant and its
+/// shared-with-me link.
+#[hdk_extern]
+pub fn unshare_file(unshare_file_input: UnshareFileInput) -> ExternResult<()> {
+    revoke_shares(
+        &unshare_file_input.original_file_metadata_hash,
+        &unshare_file_input.agent,
+    )
+}
+
+/// Stores the secret of the capability grant of a file shared with the calling agent, so they can later call the
+/// author's cell. Called remotely by the author of the file, the share is refused if the sender did not author it.
+#[hdk_extern]
+pub fn receive_shared_file(receive_shared_file_input: ReceiveSharedFileInput) -> ExternResult<()> {
+    let sender = call_info()?.provenance;
+    let original_file_metadata_hash = receive_shared_file_input.original_file_metadata_hash;
+
+    let file_metadata_record = get(original_file_metadata_hash.clone(), GetOptions::default())?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Could not find the shared file"
+        ))))?;
+    FileMetadata::try_from(file_metadata_record.clone())?;
+    if file_metadata_record.action().author() != &sender {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Only the author of a file can share it"
+        ))));
+    }
+
+    create_cap_claim(CapClaimEntry {
+        tag: shared_file_claim_tag(&original_file_metadata_hash),
+        grantor: sender,
+        secret: receive_shared_file_input.secret,
+    })?;
+
+    Ok(())
+}
+
+/// Lists the files shared with the calling agent, with their latest metadata.
+#[hdk_extern]
+pub fn list_shared_with_me(_: ()) -> ExternResult<Vec<SharedFile>> {
+    let links = get_links(
+        agent_info()?.agent_initial_pubkey,
+        LinkTypes::SharedWithMe,
+        None,
+    )?;
+
+    let mut shared_files = Vec::new();
+    for link in links {
+        let shared_file = match shared_file_from_tag(&link.tag) {
+            Some(shared_file) => shared_file,
+            None => continue,
+        };
+        if let Some(file_metadata) = get_latest_file_metadata(ActionHash::from(link.target))? {
+            shared_files.push(SharedFile {
+                owner: link.author,
+                permission: shared_file.permission,
+                file_metadata,
+            });
+        }
+    }
+
+    Ok(shared_files)
+}
+
+/// Retrieves the latest metadata of a file shared with the calling agent, by calling `get_file_metadata` on the cell
+/// of its author.
+#[hdk_extern]
+pub fn get_shared_file_metadata(
+    get_shared_file_input: GetSharedFileInput,
+) -> ExternResult<Option<Record>> {
+    call_shared_file(
+        get_shared_file_input.owner,
+        &get_shared_file_input.original_file_metadata_hash,
+        "get_file_metadata",
+        get_shared_file_input.original_file_metadata_hash.clone(),
+    )
+}
+
+/// Retrieves the chunks of a file shared with the calling agent, by calling `get_file_chunks` on the cell of its
+/// author.
+#[hdk_extern]
+pub fn get_shared_file_chunks(
+    get_shared_file_input: GetSharedFileInput,
+) -> ExternResult<Vec<Record>> {
+    call_shared_file(
+        get_shared_file_input.owner,
+        &get_shared_file_input.original_file_metadata_hash,
+        "get_file_chunks",
+        get_shared_file_input.original_file_metadata_hash.clone(),
+    )
+}
+
+/// Updates a file shared with the calling agent for writing, by calling `update_file` on the cell of its author.
+#[hdk_extern]
+pub fn update_shared_file(
+    update_shared_file_input: UpdateSharedFileInput,
+) -> ExternResult<FileOutput> {
+    call_shared_file(
+        update_shared_file_input.owner,
+        &update_shared_file_input.original_file_metadata_hash,
+        "update_file",
+        UpdateFileMetadataInput {
+            original_file_metadata_hash: update_shared_file_input
+                .original_file_metadata_hash
+                .clone(),
+            new_content: update_shared_file_input.new_content,
+        },
+    )
+}
+
+/// Returns the agent calling the zome through the capability grant of a file share, or `None` when the zome is
+/// called by the calling agent themselves.
+pub fn share_caller() -> ExternResult<Option<AgentPubKey>> {
+    let call_info = call_info()?;
+
+    match call_info.cap_grant {
+        CapGrant::RemoteAgent(ZomeCallCapGrant {
+            tag,
+            access: CapAccess::Assigned { .. },
+            ..
+        }) if tag == SHARED_FILE_CAP_TAG => Ok(Some(call_info.provenance)),
+        _ => Ok(None),
+    }
+}
+
+/// Retrieves the latest version of a file for the functions that the agents a file is shared with can call. When
+/// called through a file share, the file must be shared with the calling agent, and private files are not found.
+pub fn get_readable_file_metadata(
+    original_file_metadata_hash: ActionHash,
+) -> ExternResult<Option<Record>> {
+    let caller = match share_caller()? {
+        Some(caller) => caller,
+        None => return get_latest_file_metadata(original_file_metadata_hash),
+    };
+
+    if get_shared_with_me_links(&original_file_metadata_hash, &caller)?.is_empty() {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "File is not shared with this agent"
+        ))));
+    }
+
+    get_latest_file_metadata(original_file_metadata_hash)
+}
+
+/// Checks that a file of the calling agent is shared for writing with the agent calling the zome, when it is called
+/// through a file share.
+pub fn check_write_permission(original_file_metadata_hash: &ActionHash) -> ExternResult<()> {
+    let caller = match share_caller()? {
+        Some(caller) => caller,
+        None => return Ok(()),
+    };
+
+    let can_write = get_shared_with_me_links(original_file_metadata_hash, &caller)?
+        .iter()
+        .any(|link| {
+            shared_file_from_tag(&link.tag).map_or(false, |shared_file| {
+                shared_file.permission == SharePermission::Write
+            })
+        });
+    if !can_write {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "File is not shared for writing with this agent"
+        ))));
+    }
+
+    Ok(())
+}
+
+/// Creates the capability grant allowing any agent to send the secret of a file share to the calling agent.
+/// `receive_shared_file` only stores the shares of the files authored by the sender.
+pub fn create_receive_shared_file_grant() -> ExternResult<()> {
+    let functions =
+        BTreeSet::from([(zome_info()?.name, FunctionName::from("receive_shared_file"))]);
+    create_cap_grant(CapGrantEntry {
+        tag: String::from(RECEIVE_SHARED_FILE_CAP_TAG),
+        access: CapAccess::Unrestricted,
+        functions: GrantedFunctions::Listed(functions),
+    })?;
+
+    Ok(())
+}
+
+/// Deletes the capability grants and shared-with-me links created by the calling agent to share a file with any agent.
+pub fn revoke_all_shares(original_file_metadata_hash: &ActionHash) -> ExternResult<()> {
+    let shared_with_me: ScopedLinkType = LinkTypes::SharedWithMe.try_into()?;
+    let target = AnyLinkableHash::from(original_file_metadata_hash.clone());
+
+    let mut agents: Vec<AgentPubKey> = Vec::new();
+    for record in query(ChainQueryFilter::new().action_type(ActionType::CreateLink))? {
+        if let Action::CreateLink(create_link) = record.action() {
+            if create_link.zome_index != shared_with_me.zome_index
+                || create_link.link_type != shared_with_me.zome_type
+                || create_link.target_address != target
+            {
+                continue;
+            }
+            if let Some(entry_hash) = create_link.base_address.clone().into_entry_hash() {
+                let agent = AgentPubKey::from(entry_hash);
+                if !agents.contains(&agent) {
+                    agents.push(agent);
+                }
+            }
+        }
+    }
+
+    for agent in agents {
+        revoke_shares(original_file_metadata_hash, &agent)?;
+    }
+
+    Ok(())
+}
+
+/// Deletes the capability grants and shared-with-me links created by the calling agent to share a file with an agent.
+fn revoke_shares(
+    original_file_metadata_hash: &ActionHash,
+    agent: &AgentPubKey,
+) -> ExternResult<()> {
+    for link in get_shared_with_me_links(original_file_metadata_hash, agent)? {
+        if let Some(shared_file) = shared_file_from_tag(&link.tag) {
+            delete_cap_grant(shared_file.cap_grant_hash)?;
+        }
+        delete_link(link.create_link_hash)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the shared-with-me links of an agent created by the calling agent for a file.
+fn get_shared_with_me_links(
+    original_file_metadata_hash: &ActionHash,
+    agent: &AgentPubKey,
+) -> ExternResult<Vec<Link>> {
+    let my_pub_key = agent_info()?.agent_initial_pubkey;
+    let target = AnyLinkableHash::from(original_file_metadata_hash.clone());
+
+    Ok(get_links(agent.clone(), LinkTypes::SharedWithMe, None)?
+        .into_iter()
+        .filter(|link| link.target == target && link.author == my_pub_key)
+        .collect())
+}
+
+/// Calls a function on the cell of the owner of a file shared with the calling agent, with the latest capability
+/// claim received for the file.
+fn call_shared_file<I, O>(
+    owner: AgentPubKey,
+    original_file_metadata_hash: &ActionHash,
+    function_name: &str,
+    payload: I,
+) -> ExternResult<O>
+where
+    I: serde::Serialize + std::fmt::Debug,
+    O: serde::de::DeserializeOwned + std::fmt::Debug,
+{
+    let claim_tag = shared_file_claim_tag(original_file_metadata_hash);
+    let cap_claim = query(
+        ChainQueryFilter::new()
+            .entry_type(EntryType::CapClaim)
+            .include_entries(true),
+    )?
+    .into_iter()
+    .rev()
+    .filter_map(|record| match record.entry().as_option() {
+        Some(Entry::CapClaim(cap_claim)) => Some(cap_claim.clone()),
+        _ => None,
+    })
+    .find(|cap_claim| cap_claim.grantor == owner && cap_claim.tag == claim_tag)
+    .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+        "File is not shared with this agent"
+    ))))?;
+
+    let response = call_remote(
+        owner,
+        zome_info()?.name,
+        FunctionName::from(function_name),
+        Some(cap_claim.secret),
+        payload,
+    )?;
+
+    match response {
+        ZomeCallResponse::Ok(output) => output.decode().map_err(|e| wasm_error!(e)),
+        _ => Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Could not call {} on the cell of the owner: {:?}",
+            function_name, response
+        )))),
+    }
+}
+
+/// Returns the tag of the capability claim of a file shared with the calling agent.
+fn shared_file_claim_tag(original_file_metadata_hash: &ActionHash) -> String {
+    format!("{}:{}", SHARED_FILE_CAP_TAG, original_file_metadata_hash)
+}
@@ -22,7 +22,7 @@ pub enum Signal {
         /// The entry type involved in the action.
         app_entry: EntryTypes,
         /// The original entry type before the action was performed.
-        original_app_entry: EntryTypes,
+        original_app_entry: Box<EntryTypes>,
     },
     /// Signal that is emitted when file metadata is deleted.
     FileMetadataDeleted {
@@ -31,6 +31,20 @@ pub enum Signal {
         /// The original entry type before the action was performed.
         original_app_entry: EntryTypes,
     },
+    /// Signal that is emitted when directory metadata is created.
+    DirectoryMetadataCreated {
+        /// The hashed action signed by the agent.
+        action: SignedActionHashed,
+        /// The entry type involved in the action.
+        app_entry: EntryTypes,
+    },
+    /// Signal that is emitted when directory metadata is deleted.
+    DirectoryMetadataDeleted {
+        /// The hashed action signed by the agent.
+        action: SignedActionHashed,
+        /// The original entry type before the action was performed.
+        original_app_entry: EntryTypes,
+    },
 }
 
 /// This function is triggered after the agent commits an action.
@@ -50,12 +64,22 @@ fn signal_action(action: SignedActionHashed) -> ExternResult<()> {
     match action.hashed.content.clone() {
         Action::Create(_create) => {
             let entry = get_entry_for_action(&action.hashed.hash)?;
-            if let Some(EntryTypes::FileMetadata(_)) = entry {
-                let signal = Signal::FileMetadataCreated {
-                    action,
-                    app_entry: entry.unwrap(),
-                };
-                emit_signal(&signal)?;
+            match entry {
+                Some(EntryTypes::FileMetadata(_)) => {
+                    let signal = Signal::FileMetadataCreated {
+                        action,
+                        app_entry: entry.unwrap(),
+                    };
+                    emit_signal(&signal)?;
+                }
+                Some(EntryTypes::DirectoryMetadata(_)) => {
+                    let signal = Signal::DirectoryMetadataCreated {
+                        action,
+                        app_entry: entry.unwrap(),
+                    };
+                    emit_signal(&signal)?;
+                }
+                _ => (),
             }
             Ok(())
         }
@@ -66,7 +90,7 @@ fn signal_action(action: SignedActionHashed) -> ExternResult<()> {
                 let signal = Signal::FileMetadataUpdated {
                     action,
                     app_entry: entry.unwrap(),
-                    original_app_entry: original_entry.unwrap(),
+                    original_app_entry: Box::new(original_entry.unwrap()),
                 };
                 emit_signal(&signal)?;
             }
@@ -74,12 +98,22 @@ fn signal_action(action: SignedActionHashed) -> ExternResult<()> {
         }
         Action::Delete(delete) => {
             let original_entry = get_entry_for_action(&delete.deletes_address)?;
-            if let Some(EntryTypes::FileMetadata(_)) = original_entry {
-                let signal = Signal::FileMetadataDeleted {
-                    action,
-                    original_app_entry: original_entry.unwrap(),
-                };
-                emit_signal(&signal)?;
+            match original_entry {
+                Some(EntryTypes::FileMetadata(_)) => {
+                    let signal = Signal::FileMetadataDeleted {
+                        action,
+                        original_app_entry: original_entry.unwrap(),
+                    };
+                    emit_signal(&signal)?;
+                }
+                Some(EntryTypes::DirectoryMetadata(_)) => {
+                    let signal = Signal::DirectoryMetadataDeleted {
+                        action,
+                        original_app_entry: original_entry.unwrap(),
+                    };
+                    emit_signal(&signal)?;
+                }
+                _ => (),
             }
             Ok(())
         }
@@ -112,9 +146,5 @@ fn get_entry_for_action(action_hash: &ActionHash) -> ExternResult<Option<EntryTy
             return Ok(None);
         }
     };
-    Ok(EntryTypes::deserialize_from_type(
-        zome_index.clone(),
-        entry_index.clone(),
-        entry,
-    )?)
+    EntryTypes::deserialize_from_type(*zome_index, *entry_index, entry)
 }
@@ -0,0 +1,114 @@
+//! This module provides a trash bin for files. Trashed files are detached from the file system tree
+//! and linked to the agent who trashed them, so they can be listed, restored at their original path,
+//! or permanently deleted.
+
+use crate::delete_file;
+use crate::files::*;
+use crate::private_files::check_not_private;
+use file_system_integrity::*;
+use hdk::prelude::*;
+
+/// Moves a file to the trash of the calling agent. The file keeps its original path in its metadata.
+#[hdk_extern]
+pub fn trash_file(original_file_metadata_hash: ActionHash) -> ExternResult<Record> {
+    let file_metadata_record = get_latest_file_metadata(original_file_metadata_hash.clone())?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Could not find the file metadata"
+        ))))?;
+    check_not_private(&file_metadata_record)?;
+    let file_metadata = FileMetadata::try_from(file_metadata_record.clone())?;
+
+    if get_trash_link(original_file_metadata_hash.clone())?.is_some() {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "File is already in the trash"
+        ))));
+    }
+
+    unlink_file_metadata_from_path(original_file_metadata_hash.clone(), &file_metadata.path)?;
+
+    create_link(
+        agent_info()?.agent_initial_pubkey,
+        original_file_metadata_hash,
+        LinkTypes::AgentToTrashedFileMetaData,
+        (),
+    )?;
+
+    Ok(file_metadata_record)
+}
+
+/// Lists the latest file metadata of every file in the trash of the calling agent.
+#[hdk_extern]
+pub fn list_trash(_: ()) -> ExternResult<Vec<Record>> {
+    let mut files = Vec::new();
+
+    for link in get_trash_links()? {
+        if let Some(file_metadata) = get_latest_file_metadata(ActionHash::from(link.target))? {
+            files.push(file_metadata);
+        }
+    }
+
+    Ok(files)
+}
+
+/// Restores a file from the trash of the calling agent by linking it again at its original path.
+#[hdk_extern]
+pub fn restore_from_trash(original_file_metadata_hash: ActionHash) -> ExternResult<Record> {
+    let trash_link = get_trash_link(original_file_metadata_hash.clone())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from("File is not in the trash"))
+    ))?;
+
+    let file_metadata_record = get_latest_file_metadata(original_file_metadata_hash.clone())?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Could not find the file metadata"
+        ))))?;
+    let file_metadata = FileMetadata::try_from(file_metadata_record.clone())?;
+
+    if get_file_metadata_by_path_and_name(file_metadata.path.clone(), file_metadata.name).is_ok() {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "A file already exists at the original path"
+        ))));
+    }
+
+    delete_link(trash_link.create_link_hash)?;
+
+    link_file_metadata_to_path(
+        original_file_metadata_hash,
+        file_metadata_record.action_address().clone(),
+        &file_metadata.path,
+    )?;
+
+    Ok(file_metadata_record)
+}
+
+/// Permanently deletes every file in the trash of the calling agent, with all their versions and file chunks.
+#[hdk_extern]
+pub fn empty_trash(_: ()) -> ExternResult<Vec<ActionHash>> {
+    let mut delete_actions: Vec<ActionHash> = Vec::new();
+
+    for link in get_trash_links()? {
+        let original_file_metadata_hash = ActionHash::from(link.target);
+        if get_latest_file_metadata(original_file_metadata_hash.clone())?.is_some() {
+            delete_actions.extend(delete_file(original_file_metadata_hash)?);
+        }
+        delete_actions.push(delete_link(link.create_link_hash)?);
+    }
+
+    Ok(delete_actions)
+}
+
+/// Retrieves the links from the calling agent to the files in their trash.
+fn get_trash_links() -> ExternResult<Vec<Link>> {
+    get_links(
+        agent_info()?.agent_initial_pubkey,
+        LinkTypes::AgentToTrashedFileMetaData,
+        None,
+    )
+}
+
+/// Retrieves the link from the calling agent to the specified file, if it is in their trash.
+fn get_trash_link(original_file_metadata_hash: ActionHash) -> ExternResult<Option<Link>> {
+    let target: AnyLinkableHash = original_file_metadata_hash.into();
+    Ok(get_trash_links()?
+        .into_iter()
+        .find(|link| link.target == target))
+}
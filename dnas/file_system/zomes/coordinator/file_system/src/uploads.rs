@@ -0,0 +1,244 @@
+//! This module provides an upload session API, allowing a file to be uploaded chunk by chunk
+//! in separate zome calls instead of sending its whole content at once.
+
+use crate::files::*;
+use crate::homes::resolve_fs_path;
+use crate::quotas::check_upload_quota;
+use file_system_integrity::*;
+use hdk::prelude::*;
+
+/// A struct representing the input for beginning an upload session in the File Storage zome.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct BeginUploadInput {
+    pub name: String,
+    pub path: String,
+    pub file_type: String,
+}
+
+/// A struct representing the input for uploading one chunk of a file in the File Storage zome.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct UploadChunkInput {
+    pub session: ActionHash,
+    pub index: u32,
+    pub bytes: SerializedBytes,
+}
+
+/// Begins an upload session for a new file and returns the session id.
+#[hdk_extern]
+pub fn begin_upload(begin_upload_input: BeginUploadInput) -> ExternResult<ActionHash> {
+    let path = resolve_fs_path(&begin_upload_input.path)?;
+    let name = normalize_name(&begin_upload_input.name);
+
+    if get_file_metadata_by_path_and_name(path.clone(), name.clone()).is_ok() {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "File already exists"
+        ))));
+    }
+
+    let upload_session = UploadSession {
+        name,
+        author: agent_info()?.agent_initial_pubkey,
+        path,
+        file_type: begin_upload_input.file_type,
+        created: sys_time()?,
+    };
+
+    create_entry(&EntryTypes::UploadSession(upload_session))
+}
+
+/// Stores one chunk of a file for an upload session. Uploading the same index again replaces the previous chunk.
+/// The chunks of unfinished upload sessions count towards the bytes quota of the agent.
+#[hdk_extern]
+pub fn upload_chunk(upload_chunk_input: UploadChunkInput) -> ExternResult<Record> {
+    let session = upload_chunk_input.session;
+    get_upload_session(session.clone())?;
+
+    if upload_chunk_input.bytes.bytes().len() > file_system_properties()?.max_chunk_size() {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Chunk is larger than the maximum chunk size"
+        ))));
+    }
+
+    let tag = chunk_index_tag(upload_chunk_input.index);
+    let previous_links = get_links(
+        session.clone(),
+        LinkTypes::UploadSessionToFileChunk,
+        Some(tag.clone()),
+    )?;
+    for link in previous_links {
+        delete_link(link.create_link_hash)?;
+    }
+
+    let file_chunk = FileChunk(upload_chunk_input.bytes);
+    check_upload_quota(&file_chunk)?;
+    let chunk_record = create_file_chunk(file_chunk.clone())?;
+
+    create_link(
+        session,
+        hash_entry(&file_chunk)?,
+        LinkTypes::UploadSessionToFileChunk,
+        tag,
+    )?;
+
+    Ok(chunk_record)
+}
+
+/// Finishes an upload session by creating the file metadata with the uploaded chunks, ordered by index.
+#[hdk_extern]
+pub fn finish_upload(session: ActionHash) -> ExternResult<Record> {
+    let upload_session = get_upload_session(session.clone())?;
+
+    if get_file_metadata_by_path_and_name(upload_session.path.clone(), upload_session.name.clone())
+        .is_ok()
+    {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "File already exists"
+        ))));
+    }
+
+    let links = get_links(session.clone(), LinkTypes::UploadSessionToFileChunk, None)?;
+    let mut indexed_chunks_hashes = Vec::new();
+    for link in links.iter() {
+        let index = chunk_index_from_tag(&link.tag)?;
+        indexed_chunks_hashes.push((index, EntryHash::from(link.target.clone())));
+    }
+    indexed_chunks_hashes.sort_by_key(|(index, _)| *index);
+
+    let mut size = 0;
+    let mut chunks_hashes = Vec::new();
+    let mut chunks_sizes = Vec::new();
+    let mut content_hasher = ContentHasher::default();
+    for (expected_index, (index, chunk_hash)) in indexed_chunks_hashes.into_iter().enumerate() {
+        if index as usize != expected_index {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "Missing chunk at index {}",
+                expected_index
+            ))));
+        }
+        let file_chunk = FileChunk::try_from(get_file_chunk(chunk_hash.clone())?)?;
+        let chunk_size = file_chunk.0.bytes().len();
+        content_hasher.update(file_chunk.0.bytes());
+        size += chunk_size;
+        chunks_hashes.push(chunk_hash);
+        chunks_sizes.push(chunk_size);
+    }
+
+    let now = sys_time()?;
+    let checksum = content_hasher.finalize();
+    let file_metadata = FileMetadata {
+        name: upload_session.name,
+        author: upload_session.author,
+        path: upload_session.path,
+        created: now,
+        last_modified: now,
+        size,
+        stored_size: size,
+        file_type: upload_session.file_type,
+        chunks_hashes,
+        content_sizes: chunks_sizes.clone(),
+        chunks_sizes,
+        checksum: checksum.clone(),
+        content_checksum: Some(checksum),
+        encryption: None,
+        compression: None,
+    };
+
+    let record = create_file_metadata(file_metadata)?;
+
+    for link in links {
+        delete_link(link.create_link_hash)?;
+    }
+    delete_entry(session)?;
+
+    Ok(record)
+}
+
+/// Cancels an upload session, so that its chunks no longer count towards the quotas of the agent.
+#[hdk_extern]
+pub fn cancel_upload(session: ActionHash) -> ExternResult<()> {
+    get_upload_session(session.clone())?;
+
+    for link in get_links(session.clone(), LinkTypes::UploadSessionToFileChunk, None)? {
+        delete_link(link.create_link_hash)?;
+    }
+    delete_entry(session)?;
+
+    Ok(())
+}
+
+/// Retrieves the hashes of the file chunks linked to the live upload sessions of the calling agent.
+pub fn get_uploading_chunks_hashes() -> ExternResult<Vec<EntryHash>> {
+    let mut chunks_hashes = Vec::new();
+
+    let sessions_records = query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::UploadSession.try_into()?)
+            .action_type(ActionType::Create),
+    )?;
+
+    for session_record in sessions_records {
+        let session = session_record.signed_action.hashed.hash;
+        if get(session.clone(), GetOptions::default())?.is_none() {
+            continue;
+        }
+        for link in get_links(session, LinkTypes::UploadSessionToFileChunk, None)? {
+            chunks_hashes.push(EntryHash::from(link.target));
+        }
+    }
+
+    Ok(chunks_hashes)
+}
+
+/// Retrieves an upload session, failing if it does not exist or was not begun by the calling agent.
+fn get_upload_session(session: ActionHash) -> ExternResult<UploadSession> {
+    let record = get(session, GetOptions::default())?.ok_or(wasm_error!(WasmErrorInner::Guest(
+        String::from("Upload session not found")
+    )))?;
+    let upload_session = UploadSession::try_from(record)?;
+
+    if upload_session.author != agent_info()?.agent_initial_pubkey {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Upload session belongs to another agent"
+        ))));
+    }
+
+    Ok(upload_session)
+}
+
+/// Encodes the index of a chunk into the tag of its upload session link.
+fn chunk_index_tag(index: u32) -> LinkTag {
+    LinkTag::new(index.to_be_bytes().to_vec())
+}
+
+/// Decodes the index of a chunk from the tag of its upload session link.
+fn chunk_index_from_tag(tag: &LinkTag) -> ExternResult<u32> {
+    let bytes: [u8; 4] = tag.0.as_slice().try_into().map_err(|_| {
+        wasm_error!(WasmErrorInner::Guest(String::from(
+            "Invalid chunk index in upload session link"
+        )))
+    })?;
+
+    Ok(u32::from_be_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_index_tag() {
+        let cases = vec![0, 1, 255, 256, u32::MAX];
+
+        for index in cases {
+            let tag = chunk_index_tag(index);
+            assert_eq!(
+                chunk_index_from_tag(&tag).unwrap(),
+                index,
+                "Failed for input: {:?}",
+                index
+            );
+        }
+
+        assert!(chunk_index_from_tag(&LinkTag::new(vec![1, 2, 3])).is_err());
+    }
+}
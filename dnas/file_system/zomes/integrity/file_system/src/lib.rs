@@ -3,6 +3,9 @@
 
 use hdi::prelude::*;
 
+mod properties;
+pub use properties::*;
+
 /// Defines link types for the integrity zome.
 #[derive(Serialize, Deserialize)]
 #[hdk_link_types]
@@ -10,6 +13,12 @@ pub enum LinkTypes {
     PathFileSystem,
     PathToFileMetaData,
     FileMetaDataUpdate,
+    PathToDirectoryMetaData,
+    AgentToTrashedFileMetaData,
+    UploadSessionToFileChunk,
+    FileChunkToFileMetaData,
+    SharedWithMe,
+    AgentToEncryptionKey,
 }
 
 /// Defines entry types for the integrity zome.
@@ -20,6 +29,15 @@ pub enum LinkTypes {
 pub enum EntryTypes {
     FileMetadata(FileMetadata),
     FileChunk(FileChunk),
+    DirectoryMetadata(DirectoryMetadata),
+    UploadSession(UploadSession),
+    EncryptionKey(EncryptionKey),
+    /// Metadata of a private file, stored only on the source chain of its author.
+    #[entry_def(visibility = "private")]
+    PrivateFileMetadata(FileMetadata),
+    /// Chunk of a private file, stored only on the source chain of its author.
+    #[entry_def(visibility = "private")]
+    PrivateFileChunk(FileChunk),
 }
 
 /// File chunk entry type.
@@ -37,50 +55,1721 @@ pub struct FileMetadata {
     pub created: Timestamp,
     pub last_modified: Timestamp,
     pub size: usize,
+    /// Total size of the chunks as stored, after compression and encryption.
+    pub stored_size: usize,
     pub file_type: String,
     pub chunks_hashes: Vec<EntryHash>,
+    pub chunks_sizes: Vec<usize>,
+    /// Size of the content of each chunk, before compression. Empty for encrypted files, so that they do not reveal
+    /// the boundaries of their content.
+    pub content_sizes: Vec<usize>,
+    /// Checksum of the chunks as stored, after compression and encryption.
+    pub checksum: String,
+    /// Checksum of the content of the file, before compression, so that clients can check the content they reassemble.
+    /// Not set on encrypted files, since anyone could confirm a guess of their content with it.
+    pub content_checksum: Option<String>,
+    /// Keys to decrypt the chunks of the file, if its content is encrypted.
+    #[serde(default)]
+    pub encryption: Option<FileEncryption>,
+    /// Codec the chunks of the file are compressed with, if any. Chunks are compressed before being encrypted.
+    #[serde(default)]
+    pub compression: Option<CompressionCodec>,
+}
+
+impl FileMetadata {
+    /// Returns the size of the content held by each chunk, as recorded in the metadata for files that are not
+    /// encrypted, or without the nonce and authentication tag of the stored chunks for encrypted files. Returns `None`
+    /// for encrypted compressed files, whose content sizes are not published.
+    pub fn content_chunks_sizes(&self) -> Option<Vec<usize>> {
+        if self.encryption.is_some() {
+            return match self.compression {
+                Some(_) => None,
+                None => Some(
+                    self.chunks_sizes
+                        .iter()
+                        .map(|chunk_size| chunk_size.saturating_sub(ENCRYPTED_CHUNK_OVERHEAD))
+                        .collect(),
+                ),
+            };
+        }
+
+        Some(self.content_sizes.clone())
+    }
+
+    /// Returns the largest size of a chunk as stored, allowing for the overhead of compression and encryption.
+    pub fn max_stored_chunk_size(&self, properties: &FileSystemProperties) -> usize {
+        let mut max_stored_chunk_size = properties.max_chunk_size();
+        if let Some(compression) = self.compression {
+            max_stored_chunk_size += compression.max_overhead(max_stored_chunk_size);
+        }
+        if self.encryption.is_some() {
+            max_stored_chunk_size += ENCRYPTED_CHUNK_OVERHEAD;
+        }
+        max_stored_chunk_size
+    }
+}
+
+/// Codecs the chunks of a file can be compressed with.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionCodec {
+    /// Raw DEFLATE stream, as specified by RFC 1951.
+    Deflate,
+}
+
+impl CompressionCodec {
+    /// Returns the largest number of bytes the codec can add to content of the given size that does not compress.
+    pub fn max_overhead(&self, size: usize) -> usize {
+        match self {
+            // Stored blocks of at most 65535 bytes, each with a 5 bytes header
+            CompressionCodec::Deflate => (size / 65535 + 1) * 5 + 16,
+        }
+    }
+
+    /// Decompresses compressed bytes, returning `None` if they are not valid for the codec or if their content is
+    /// larger than the given maximum size.
+    pub fn decompress(&self, bytes: &[u8], max_size: usize) -> Option<Vec<u8>> {
+        match self {
+            CompressionCodec::Deflate => {
+                miniz_oxide::inflate::decompress_to_vec_with_limit(bytes, max_size).ok()
+            }
+        }
+    }
+}
+
+/// Number of bytes an encrypted chunk adds to its content: the nonce followed by the authentication tag of the
+/// XSalsa20Poly1305 secretbox.
+pub const ENCRYPTED_CHUNK_OVERHEAD: usize =
+    holochain_integrity_types::x_salsa20_poly1305::nonce::NONCE_BYTES + 16;
+
+/// Keys of an encrypted file. The chunks of the file are encrypted with a random symmetric key, which is wrapped for
+/// each agent allowed to read the file with the X25519 keys of the author and of the agent.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FileEncryption {
+    pub sender: X25519PubKey,
+    pub wrapped_keys: Vec<WrappedFileKey>,
+}
+
+/// Symmetric key of an encrypted file, wrapped for an agent allowed to read it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WrappedFileKey {
+    pub agent: AgentPubKey,
+    pub recipient: X25519PubKey,
+    pub wrapped_key: XSalsa20Poly1305EncryptedData,
+}
+
+/// Encryption key entry type, publishing the X25519 public key of an agent so that files can be encrypted for them.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct EncryptionKey(pub X25519PubKey);
+
+/// Directory metadata entry type.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct DirectoryMetadata {
+    pub name: String,
+    pub author: AgentPubKey,
+    pub path: String,
+    pub created: Timestamp,
+    pub last_modified: Timestamp,
+}
+
+/// Upload session entry type, describing a file being uploaded chunk by chunk.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct UploadSession {
+    pub name: String,
+    pub author: AgentPubKey,
+    pub path: String,
+    pub file_type: String,
+    pub created: Timestamp,
+}
+
+/// Hasher computing the checksum of a file content, a hex-encoded BLAKE2b-256 digest.
+/// The content can be fed chunk by chunk, in order.
+pub struct ContentHasher(blake2b_simd::State);
+
+impl Default for ContentHasher {
+    fn default() -> Self {
+        ContentHasher(blake2b_simd::Params::new().hash_length(32).to_state())
+    }
+}
+
+impl ContentHasher {
+    /// Adds the next bytes of the content to the checksum.
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    /// Returns the checksum of the content added so far.
+    pub fn finalize(&self) -> String {
+        self.0.finalize().to_hex().to_string()
+    }
+}
+
+/// Mirror of the hdk `Path` type, so that the entry hash of a file system path can be computed during validation.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug)]
+struct DhtPath(Vec<DhtPathComponent>);
+
+/// Mirror of the hdk `Component` type, holding the UTF-32 encoded name of a path component.
+#[derive(Serialize, Deserialize, Debug)]
+struct DhtPathComponent(#[serde(with = "serde_bytes")] Vec<u8>);
+
+/// Returns the path entry of a standardized file system path, as the hdk `Path` of its DHT-style path,
+/// whose components are the name keys of its folders.
+pub fn file_system_path_entry(
+    path: &str,
+    properties: &FileSystemProperties,
+) -> ExternResult<Entry> {
+    let components = std::iter::once(String::from("root"))
+        .chain(
+            path.split('/')
+                .filter(|part| !part.is_empty())
+                .map(|part| properties.name_key(part)),
+        )
+        .map(|part| {
+            DhtPathComponent(
+                part.chars()
+                    .flat_map(|c| (c as u32).to_le_bytes())
+                    .collect(),
+            )
+        })
+        .collect();
+
+    Ok(Entry::App(AppEntryBytes(
+        SerializedBytes::try_from(DhtPath(components)).map_err(|e| wasm_error!(e))?,
+    )))
+}
+
+/// Encodes the hash of the file metadata version that placed a file at a path into the tag of its path link.
+pub fn file_metadata_version_tag(file_metadata_hash: &ActionHash) -> LinkTag {
+    LinkTag::new(file_metadata_hash.get_raw_39().to_vec())
+}
+
+/// Decodes the hash of a file metadata version from the tag of a path link, if any.
+pub fn file_metadata_version_from_tag(tag: &LinkTag) -> Option<ActionHash> {
+    if tag.0.len() != 39 {
+        return None;
+    }
+    ActionHash::from_raw_39(tag.0.clone()).ok()
+}
+
+/// Permission given to an agent a file is shared with.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SharePermission {
+    /// The agent can read the file through the author's cell.
+    Read,
+    /// The agent can also update the file through the author's cell.
+    Write,
+}
+
+/// Content of the tag of a shared-with-me link: the permission given to the agent, and the capability grant
+/// created by the author of the file for it.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct SharedFileTag {
+    pub permission: SharePermission,
+    pub cap_grant_hash: ActionHash,
+}
+
+/// Encodes a `SharedFileTag` into the tag of a shared-with-me link.
+pub fn shared_file_tag(shared_file_tag: SharedFileTag) -> ExternResult<LinkTag> {
+    let bytes = SerializedBytes::try_from(shared_file_tag).map_err(|e| wasm_error!(e))?;
+    Ok(LinkTag::new(bytes.bytes().clone()))
+}
+
+/// Decodes a `SharedFileTag` from the tag of a shared-with-me link, if it is well formed.
+pub fn shared_file_from_tag(tag: &LinkTag) -> Option<SharedFileTag> {
+    SharedFileTag::try_from(SerializedBytes::from(UnsafeBytes::from(tag.0.clone()))).ok()
 }
 
 /// Validates the provided `Op` to ensure the entry and link types adhere to the defined constraints.
 #[hdk_extern]
 pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
-    if let FlatOp::StoreEntry(store_entry) = op.flattened::<EntryTypes, LinkTypes>()? {
-        match store_entry {
-            OpEntry::CreateEntry { app_entry, .. } | OpEntry::UpdateEntry { app_entry, .. } => {
-                match app_entry {
-                    EntryTypes::FileMetadata(file_metadata) => {
-                        return validate_create_file_metadata(file_metadata);
-                    }
-                    EntryTypes::FileChunk(_) => return Ok(ValidateCallbackResult::Valid),
+    match op.flattened::<EntryTypes, LinkTypes>()? {
+        FlatOp::StoreEntry(OpEntry::CreateEntry { app_entry, action }) => match app_entry {
+            EntryTypes::FileMetadata(file_metadata) => {
+                validate_create_file_metadata(file_metadata, &action.author)
+            }
+            EntryTypes::FileChunk(_) => Ok(ValidateCallbackResult::Valid),
+            EntryTypes::DirectoryMetadata(directory_metadata) => {
+                validate_create_directory_metadata(directory_metadata, &action.author)
+            }
+            EntryTypes::UploadSession(_) => Ok(ValidateCallbackResult::Valid),
+            EntryTypes::EncryptionKey(_) => Ok(ValidateCallbackResult::Valid),
+            // Private entries are never published, so their content is not validated
+            EntryTypes::PrivateFileMetadata(_) => Ok(ValidateCallbackResult::Valid),
+            EntryTypes::PrivateFileChunk(_) => Ok(ValidateCallbackResult::Valid),
+        },
+        FlatOp::StoreEntry(OpEntry::UpdateEntry {
+            app_entry,
+            action,
+            original_action_hash,
+            ..
+        }) => match app_entry {
+            EntryTypes::FileMetadata(file_metadata) => {
+                let result = validate_update_file_metadata(&file_metadata, original_action_hash)?;
+                if result != ValidateCallbackResult::Valid {
+                    return Ok(result);
                 }
+                validate_create_file_metadata(file_metadata, &action.author)
+            }
+            EntryTypes::FileChunk(_) => Ok(ValidateCallbackResult::Valid),
+            EntryTypes::DirectoryMetadata(directory_metadata) => {
+                validate_create_directory_metadata(directory_metadata, &action.author)
+            }
+            EntryTypes::UploadSession(_) => Ok(ValidateCallbackResult::Valid),
+            EntryTypes::EncryptionKey(_) => Ok(ValidateCallbackResult::Valid),
+            EntryTypes::PrivateFileMetadata(_) => Ok(ValidateCallbackResult::Valid),
+            EntryTypes::PrivateFileChunk(_) => Ok(ValidateCallbackResult::Valid),
+        },
+        FlatOp::RegisterUpdate(OpUpdate::Entry {
+            original_app_entry: EntryTypes::FileMetadata(original_file_metadata),
+            action,
+            ..
+        }) => validate_file_metadata_author(&original_file_metadata, &action.author),
+        FlatOp::RegisterDelete(OpDelete::Entry {
+            original_app_entry: EntryTypes::FileMetadata(original_file_metadata),
+            action,
+            ..
+        }) => validate_file_metadata_author(&original_file_metadata, &action.author),
+        FlatOp::RegisterDelete(OpDelete::Entry {
+            original_action,
+            original_app_entry: EntryTypes::FileChunk(_),
+            action,
+        }) => validate_file_chunk_author(original_action.author(), &action.author),
+        FlatOp::RegisterUpdate(OpUpdate::PrivateEntry {
+            original_action_hash,
+            original_app_entry_type: UnitEntryTypes::PrivateFileMetadata,
+            action,
+            ..
+        }) => validate_private_file_metadata_author(
+            must_get_action(original_action_hash)?.action().author(),
+            &action.author,
+        ),
+        FlatOp::RegisterDelete(OpDelete::PrivateEntry {
+            original_action,
+            original_app_entry_type: UnitEntryTypes::PrivateFileMetadata,
+            action,
+        }) => validate_private_file_metadata_author(original_action.author(), &action.author),
+        FlatOp::RegisterCreateLink {
+            base_address,
+            target_address,
+            tag,
+            link_type,
+            action,
+        } => match link_type {
+            LinkTypes::PathToFileMetaData => validate_create_link_path_to_file_metadata(
+                base_address,
+                target_address,
+                tag,
+                &action.author,
+                &HostLookup,
+            ),
+            LinkTypes::FileMetaDataUpdate => validate_create_link_file_metadata_update(
+                base_address,
+                target_address,
+                &action.author,
+                &HostLookup,
+            ),
+            LinkTypes::FileChunkToFileMetaData => validate_create_link_file_chunk_to_file_metadata(
+                base_address,
+                target_address,
+                &action.author,
+                &HostLookup,
+            ),
+            LinkTypes::SharedWithMe => validate_create_link_shared_with_me(
+                base_address,
+                target_address,
+                tag,
+                &action.author,
+            ),
+            LinkTypes::AgentToEncryptionKey => validate_create_link_agent_to_encryption_key(
+                base_address,
+                target_address,
+                &action.author,
+            ),
+            _ => Ok(ValidateCallbackResult::Valid),
+        },
+        FlatOp::RegisterDeleteLink {
+            original_action,
+            link_type:
+                LinkTypes::PathToFileMetaData
+                | LinkTypes::FileMetaDataUpdate
+                | LinkTypes::FileChunkToFileMetaData
+                | LinkTypes::SharedWithMe
+                | LinkTypes::AgentToEncryptionKey,
+            action,
+            ..
+        } => {
+            if original_action.author != action.author {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Only the author of a file link can delete it".into(),
+                ));
             }
-            _ => (),
+            Ok(ValidateCallbackResult::Valid)
         }
+        _ => Ok(ValidateCallbackResult::Valid),
     }
-    Ok(ValidateCallbackResult::Valid)
 }
 
-/// Validates the `FileMetadata` for its creation or update, ensuring the name and path meet the defined constraints.
+/// Validates the `FileMetadata` for its creation or update, ensuring the author, name and path meet the defined constraints.
 fn validate_create_file_metadata(
     file_metadata: FileMetadata,
+    action_author: &AgentPubKey,
 ) -> ExternResult<ValidateCallbackResult> {
+    if &file_metadata.author != action_author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "File metadata author must be the author of the action".into(),
+        ));
+    }
+
     if file_metadata.name.is_empty() {
         return Ok(ValidateCallbackResult::Invalid(
             "File name cannot be empty".into(),
         ));
     }
 
-    if has_forbidden_chars(file_metadata.path.as_str()) {
+    let properties = file_system_properties()?;
+
+    if properties.exceeds_name_length(&file_metadata.name) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "File name is longer than the maximum name length".into(),
+        ));
+    }
+
+    if properties.exceeds_path_name_length(&file_metadata.path) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "File path has a folder name longer than the maximum name length".into(),
+        ));
+    }
+
+    if !is_normalized_name(&file_metadata.name) || !is_normalized_name(&file_metadata.path) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "File name and path must be in Unicode NFC normalization form".into(),
+        ));
+    }
+
+    if is_relative_name(&file_metadata.name) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "File name cannot be \".\" or \"..\"".into(),
+        ));
+    }
+
+    if properties.has_forbidden_chars(file_metadata.path.as_str())
+        || has_relative_components(&file_metadata.path)
+    {
         return Ok(ValidateCallbackResult::Invalid(
             "File path cannot contain forbidden characters".into(),
         ));
     }
 
+    if properties.home_directories
+        && !is_in_home_directory(&file_metadata.path, &file_metadata.author)
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "File path must be in the home folder of its author".into(),
+        ));
+    }
+
+    if properties.exceeds_path_depth(path_depth(&file_metadata.path)) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "File path is deeper than the maximum path depth".into(),
+        ));
+    }
+
+    if let Some(encryption) = &file_metadata.encryption {
+        if !encryption
+            .wrapped_keys
+            .iter()
+            .any(|wrapped_key| &wrapped_key.agent == action_author)
+        {
+            return Ok(ValidateCallbackResult::Invalid(
+                "The key of an encrypted file must be wrapped for its author".into(),
+            ));
+        }
+    }
+
+    if properties.exceeds_file_size(file_metadata.size) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "File is larger than the maximum file size".into(),
+        ));
+    }
+
+    validate_file_metadata_chunks(&file_metadata, &properties)
+}
+
+/// Validates that the chunks hashes of a `FileMetadata` point to `FileChunk` entries whose sizes and content match
+/// the declared chunks sizes, content sizes, file size and checksums.
+fn validate_file_metadata_chunks(
+    file_metadata: &FileMetadata,
+    properties: &FileSystemProperties,
+) -> ExternResult<ValidateCallbackResult> {
+    let file_chunks = file_metadata.chunks_hashes.iter().map(|chunk_hash| {
+        let entry = must_get_entry(chunk_hash.clone())?.content;
+        Ok(match entry {
+            Entry::App(_) => FileChunk::try_from(entry).ok(),
+            _ => None,
+        })
+    });
+
+    check_file_metadata_chunks(file_metadata, file_chunks, properties)
+}
+
+/// Checks the chunks of a `FileMetadata`, given in order, against its declared chunks sizes, content sizes, file size
+/// and checksums. A `None` chunk is an entry that is not a `FileChunk`. The chunks are checked one by one, so that a
+/// large file is never held in memory as a whole. Compressed chunks that are not encrypted are decompressed to check
+/// their content, while only the declared content sizes of encrypted compressed chunks can be checked.
+fn check_file_metadata_chunks(
+    file_metadata: &FileMetadata,
+    file_chunks: impl Iterator<Item = ExternResult<Option<FileChunk>>>,
+    properties: &FileSystemProperties,
+) -> ExternResult<ValidateCallbackResult> {
+    if file_metadata.chunks_sizes.len() != file_metadata.chunks_hashes.len() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "File chunks sizes must match the file chunks hashes".into(),
+        ));
+    }
+
+    if file_metadata.encryption.is_some()
+        && (file_metadata.content_checksum.is_some() || !file_metadata.content_sizes.is_empty())
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Encrypted files cannot publish the checksum or sizes of their content".into(),
+        ));
+    }
+
+    if file_metadata.encryption.is_none() && file_metadata.content_checksum.is_none() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Files that are not encrypted must have a content checksum".into(),
+        ));
+    }
+
+    // The content sizes of encrypted compressed files are not published
+    let content_chunks_sizes = match file_metadata.content_chunks_sizes() {
+        Some(content_chunks_sizes)
+            if content_chunks_sizes.len() == file_metadata.chunks_hashes.len() =>
+        {
+            Some(content_chunks_sizes)
+        }
+        None if file_metadata.encryption.is_some() => None,
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "File content sizes must match the file chunks hashes".into(),
+            ))
+        }
+    };
+
+    if file_metadata.encryption.is_none()
+        && file_metadata.compression.is_none()
+        && file_metadata.content_sizes != file_metadata.chunks_sizes
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "File content sizes do not match the sizes of its chunks".into(),
+        ));
+    }
+
+    if content_chunks_sizes
+        .iter()
+        .flatten()
+        .any(|content_size| *content_size > properties.max_chunk_size())
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "File chunk content is larger than the maximum chunk size".into(),
+        ));
+    }
+
+    let max_chunk_size = file_metadata.max_stored_chunk_size(properties);
+    let decompress = match file_metadata.encryption {
+        Some(_) => None,
+        None => file_metadata.compression,
+    };
+
+    let mut stored_hasher = ContentHasher::default();
+    let mut content_hasher = ContentHasher::default();
+    for (index, (file_chunk, chunk_size)) in file_chunks
+        .zip(file_metadata.chunks_sizes.iter())
+        .enumerate()
+    {
+        let file_chunk = match file_chunk? {
+            Some(file_chunk) => file_chunk,
+            None => {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "File chunks hashes must point to file chunks".into(),
+                ))
+            }
+        };
+
+        let chunk_bytes = file_chunk.0.bytes();
+        if chunk_bytes.len() > max_chunk_size {
+            return Ok(ValidateCallbackResult::Invalid(
+                "File chunk is larger than the maximum chunk size".into(),
+            ));
+        }
+        if chunk_bytes.len() != *chunk_size {
+            return Ok(ValidateCallbackResult::Invalid(
+                "File chunk size does not match its declared size".into(),
+            ));
+        }
+        stored_hasher.update(chunk_bytes);
+
+        match decompress {
+            Some(codec) => {
+                let content = match codec.decompress(chunk_bytes, properties.max_chunk_size()) {
+                    Some(content) => content,
+                    None => {
+                        return Ok(ValidateCallbackResult::Invalid(
+                            "File chunk could not be decompressed".into(),
+                        ))
+                    }
+                };
+                let content_size = content_chunks_sizes
+                    .as_ref()
+                    .and_then(|content_chunks_sizes| content_chunks_sizes.get(index));
+                if content_size != Some(&content.len()) {
+                    return Ok(ValidateCallbackResult::Invalid(
+                        "File chunk content size does not match its declared size".into(),
+                    ));
+                }
+                content_hasher.update(&content);
+            }
+            None => content_hasher.update(chunk_bytes),
+        }
+    }
+
+    if file_metadata.chunks_sizes.iter().sum::<usize>() != file_metadata.stored_size {
+        return Ok(ValidateCallbackResult::Invalid(
+            "File stored size does not match the total size of its chunks".into(),
+        ));
+    }
+
+    match &content_chunks_sizes {
+        Some(content_chunks_sizes) => {
+            if content_chunks_sizes.iter().sum::<usize>() != file_metadata.size {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "File size does not match the total size of its chunks".into(),
+                ));
+            }
+        }
+        None => {
+            if file_metadata.size > file_metadata.chunks_hashes.len() * properties.max_chunk_size()
+            {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "File size is larger than its chunks can hold".into(),
+                ));
+            }
+        }
+    }
+
+    if stored_hasher.finalize() != file_metadata.checksum {
+        return Ok(ValidateCallbackResult::Invalid(
+            "File checksum does not match the content of its chunks".into(),
+        ));
+    }
+
+    // Only the files that are not encrypted have a content checksum, as the validating agent can read their content
+    if let Some(content_checksum) = &file_metadata.content_checksum {
+        if content_checksum != &content_hasher.finalize() {
+            return Ok(ValidateCallbackResult::Invalid(
+                "File content checksum does not match the content of its chunks".into(),
+            ));
+        }
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Validates that an update of a `FileMetadata` keeps the immutable fields of the version it updates, that it only
+/// renames the file without changing its content, and that its last modification date comes after the one of that
+/// version.
+fn validate_update_file_metadata(
+    file_metadata: &FileMetadata,
+    original_action_hash: ActionHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let original_file_metadata = match must_get_file_metadata(original_action_hash)? {
+        Some(original_file_metadata) => original_file_metadata,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "File metadata update must point to a file metadata entry".into(),
+            ))
+        }
+    };
+
+    Ok(check_update_file_metadata(
+        file_metadata,
+        &original_file_metadata,
+    ))
+}
+
+/// Checks an update of a `FileMetadata` against the version it updates. The name of a file can only change in an
+/// update that leaves its content untouched, so that renaming a file and updating its content are separate versions.
+fn check_update_file_metadata(
+    file_metadata: &FileMetadata,
+    original_file_metadata: &FileMetadata,
+) -> ValidateCallbackResult {
+    if file_metadata.created != original_file_metadata.created
+        || file_metadata.author != original_file_metadata.author
+    {
+        return ValidateCallbackResult::Invalid(
+            "File metadata update cannot change the creation date or author".into(),
+        );
+    }
+
+    if file_metadata.name != original_file_metadata.name
+        && (file_metadata.chunks_hashes != original_file_metadata.chunks_hashes
+            || file_metadata.content_checksum != original_file_metadata.content_checksum
+            || file_metadata.size != original_file_metadata.size)
+    {
+        return ValidateCallbackResult::Invalid(
+            "File metadata update cannot change both the name and the content".into(),
+        );
+    }
+
+    if file_metadata.last_modified <= original_file_metadata.last_modified {
+        return ValidateCallbackResult::Invalid(
+            "File metadata update must have a later last modification date".into(),
+        );
+    }
+
+    ValidateCallbackResult::Valid
+}
+
+/// Validates that an update or delete of a `FileMetadata` is made by the author of the file.
+/// Other agents act on a file through the author's cell, by calling it with a capability grant.
+fn validate_file_metadata_author(
+    original_file_metadata: &FileMetadata,
+    action_author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    if &original_file_metadata.author != action_author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the author of a file can update or delete it".into(),
+        ));
+    }
+
     Ok(ValidateCallbackResult::Valid)
 }
 
-/// Checks if a given path contains any forbidden characters, returning `true` if any are found.
-fn has_forbidden_chars(path: &str) -> bool {
-    let forbidden_chars = &['<', '>', ':', '"', '|', '?', '*', '.'];
-    path.chars().any(|c| forbidden_chars.contains(&c))
+/// Validates that a delete of a `FileChunk` is made by the author of the creation it targets, since chunks with the
+/// same content are shared by the files of every agent.
+fn validate_file_chunk_author(
+    original_author: &AgentPubKey,
+    action_author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    if original_author != action_author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the author of a file chunk can delete it".into(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Validates that an update or delete of a private `FileMetadata` is made by the author of the action it targets.
+/// The content of private entries is not available to validation, so the author recorded in the metadata cannot be
+/// checked.
+fn validate_private_file_metadata_author(
+    original_author: &AgentPubKey,
+    action_author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    if original_author != action_author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the author of a private file can update or delete it".into(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Validates a `PathToFileMetaData` link, which must target the creation of a file metadata entry authored by the
+/// link author. The file path of the version referenced by the link tag, or of the original version if the tag is
+/// empty, must be the base path of the link.
+fn validate_create_link_path_to_file_metadata(
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    tag: LinkTag,
+    link_author: &AgentPubKey,
+    lookup: &impl DhtLookup,
+) -> ExternResult<ValidateCallbackResult> {
+    let original_file_metadata_hash = match target_address.into_action_hash() {
+        Some(action_hash) => action_hash,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Path to file metadata link must target a file metadata action".into(),
+            ))
+        }
+    };
+
+    let file_metadata_hash = if tag.0.is_empty() {
+        original_file_metadata_hash.clone()
+    } else {
+        match file_metadata_version_from_tag(&tag) {
+            Some(file_metadata_hash) => file_metadata_hash,
+            None => {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Path to file metadata link tag must be a file metadata version".into(),
+                ))
+            }
+        }
+    };
+
+    if !leads_to_create_action(
+        file_metadata_hash.clone(),
+        &original_file_metadata_hash,
+        lookup,
+    )? {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Path to file metadata link must target the creation of the file metadata".into(),
+        ));
+    }
+
+    let file_metadata = match lookup.file_metadata(file_metadata_hash)? {
+        Some(file_metadata) => file_metadata,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Path to file metadata link must target a file metadata entry".into(),
+            ))
+        }
+    };
+
+    if &file_metadata.author != link_author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the author of a file can link it to a path".into(),
+        ));
+    }
+
+    if lookup.path_hash(&file_metadata.path)? != base_address {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Path to file metadata link base must be the path of the file".into(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Validates a `FileMetaDataUpdate` link, which must target an update, authored by the link author,
+/// whose chain of original actions leads back to the creation of the file metadata at the base of the link.
+fn validate_create_link_file_metadata_update(
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    link_author: &AgentPubKey,
+    lookup: &impl DhtLookup,
+) -> ExternResult<ValidateCallbackResult> {
+    let (original_file_metadata_hash, file_metadata_hash) = match (
+        base_address.into_action_hash(),
+        target_address.into_action_hash(),
+    ) {
+        (Some(base), Some(target)) => (base, target),
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "File metadata update link must link file metadata actions".into(),
+            ))
+        }
+    };
+
+    match lookup.action(file_metadata_hash.clone())? {
+        Action::Update(update) if &update.author == link_author => {}
+        Action::Update(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Only the author of a file metadata update can link it".into(),
+            ))
+        }
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "File metadata update link must target an update action".into(),
+            ))
+        }
+    }
+
+    if !leads_to_create_action(file_metadata_hash, &original_file_metadata_hash, lookup)? {
+        return Ok(ValidateCallbackResult::Invalid(
+            "File metadata update link must target an update of the file metadata at its base"
+                .into(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Returns whether following the original actions of the specified action leads to the specified create action.
+fn leads_to_create_action(
+    action_hash: ActionHash,
+    create_action_hash: &ActionHash,
+    lookup: &impl DhtLookup,
+) -> ExternResult<bool> {
+    let mut action_hash = action_hash;
+    loop {
+        match lookup.action(action_hash.clone())? {
+            Action::Update(update) => action_hash = update.original_action_address.clone(),
+            Action::Create(_) => return Ok(&action_hash == create_action_hash),
+            _ => return Ok(false),
+        }
+    }
+}
+
+/// Validates a `FileChunkToFileMetaData` link, which must target a file metadata action authored by the link author
+/// whose file metadata lists the file chunk at the base of the link, so that the references counted by garbage
+/// collection cannot be forged.
+fn validate_create_link_file_chunk_to_file_metadata(
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    link_author: &AgentPubKey,
+    lookup: &impl DhtLookup,
+) -> ExternResult<ValidateCallbackResult> {
+    let (chunk_hash, file_metadata_hash) = match (
+        base_address.into_entry_hash(),
+        target_address.into_action_hash(),
+    ) {
+        (Some(base), Some(target)) => (base, target),
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "File chunk link must link a file chunk to a file metadata action".into(),
+            ))
+        }
+    };
+
+    if lookup.action(file_metadata_hash.clone())?.author() != link_author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the author of a file metadata can link file chunks to it".into(),
+        ));
+    }
+
+    match lookup.file_metadata(file_metadata_hash)? {
+        Some(file_metadata) if file_metadata.chunks_hashes.contains(&chunk_hash) => {
+            Ok(ValidateCallbackResult::Valid)
+        }
+        _ => Ok(ValidateCallbackResult::Invalid(
+            "File chunk link must target a file metadata listing the chunk".into(),
+        )),
+    }
+}
+
+/// The DHT data looked up when validating the links of a file, so that the checks can run without a host in tests.
+trait DhtLookup {
+    /// Retrieves the action with the specified hash.
+    fn action(&self, action_hash: ActionHash) -> ExternResult<Action>;
+
+    /// Retrieves the `FileMetadata` created or updated by the specified action, or `None` if the action does not
+    /// create a file metadata entry.
+    fn file_metadata(&self, action_hash: ActionHash) -> ExternResult<Option<FileMetadata>>;
+
+    /// Returns the hash of the path entry of the specified file system path.
+    fn path_hash(&self, path: &str) -> ExternResult<AnyLinkableHash>;
+}
+
+/// Looks up DHT data through the host.
+struct HostLookup;
+
+impl DhtLookup for HostLookup {
+    fn action(&self, action_hash: ActionHash) -> ExternResult<Action> {
+        Ok(must_get_action(action_hash)?.action().clone())
+    }
+
+    fn file_metadata(&self, action_hash: ActionHash) -> ExternResult<Option<FileMetadata>> {
+        must_get_file_metadata(action_hash)
+    }
+
+    fn path_hash(&self, path: &str) -> ExternResult<AnyLinkableHash> {
+        let properties = file_system_properties()?;
+        Ok(hash_entry(file_system_path_entry(path, &properties)?)?.into())
+    }
+}
+
+/// Retrieves the `FileMetadata` created or updated by the specified action, or `None` if the action
+/// does not create a file metadata entry.
+fn must_get_file_metadata(action_hash: ActionHash) -> ExternResult<Option<FileMetadata>> {
+    let action = must_get_action(action_hash)?;
+    let entry_hash = match action.action().entry_hash() {
+        Some(entry_hash) => entry_hash.clone(),
+        None => return Ok(None),
+    };
+    let entry = must_get_entry(entry_hash)?.content;
+
+    Ok(FileMetadata::try_from(entry).ok())
+}
+
+/// Validates a shared-with-me link, ensuring it links an agent to a file created by the author of the link, with a
+/// well formed tag.
+fn validate_create_link_shared_with_me(
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    tag: LinkTag,
+    link_author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let original_file_metadata_hash = match (
+        base_address.into_entry_hash(),
+        target_address.into_action_hash(),
+    ) {
+        (Some(_), Some(target)) => target,
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Shared file link must link an agent to a file metadata action".into(),
+            ))
+        }
+    };
+
+    if shared_file_from_tag(&tag).is_none() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Shared file link tag must hold the share permission and capability grant".into(),
+        ));
+    }
+
+    let create_action = must_get_action(original_file_metadata_hash)?;
+    match create_action.action() {
+        Action::Create(create) if &create.author == link_author => {}
+        Action::Create(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Only the author of a file can share it".into(),
+            ))
+        }
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Shared file link must target the creation of a file metadata".into(),
+            ))
+        }
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Validates an agent to encryption key link, ensuring an agent can only publish their own encryption keys.
+fn validate_create_link_agent_to_encryption_key(
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    link_author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    if base_address != AnyLinkableHash::from(link_author.clone()) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "An agent can only link encryption keys from themselves".into(),
+        ));
+    }
+
+    let encryption_key_hash = match target_address.into_action_hash() {
+        Some(target) => target,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Encryption key link must target an encryption key action".into(),
+            ))
+        }
+    };
+
+    let record = must_get_valid_record(encryption_key_hash)?;
+    if record.action().author() != link_author || EncryptionKey::try_from(record).is_err() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Encryption key link must target an encryption key created by the link author".into(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Validates the `DirectoryMetadata` for its creation or update, ensuring the author, name and path meet the defined constraints.
+fn validate_create_directory_metadata(
+    directory_metadata: DirectoryMetadata,
+    action_author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    if &directory_metadata.author != action_author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Directory metadata author must be the author of the action".into(),
+        ));
+    }
+
+    if directory_metadata.name.is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Directory name cannot be empty".into(),
+        ));
+    }
+
+    let properties = file_system_properties()?;
+
+    if properties.exceeds_name_length(&directory_metadata.name) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Directory name is longer than the maximum name length".into(),
+        ));
+    }
+
+    if properties.exceeds_path_name_length(&directory_metadata.path) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Directory path has a folder name longer than the maximum name length".into(),
+        ));
+    }
+
+    if !is_normalized_name(&directory_metadata.name)
+        || !is_normalized_name(&directory_metadata.path)
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Directory name and path must be in Unicode NFC normalization form".into(),
+        ));
+    }
+
+    if directory_metadata.name.contains(['/', '\\'])
+        || properties.has_forbidden_chars(&directory_metadata.name)
+        || is_relative_name(&directory_metadata.name)
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Directory name cannot contain separators or forbidden characters".into(),
+        ));
+    }
+
+    if properties.has_forbidden_chars(directory_metadata.path.as_str())
+        || has_relative_components(&directory_metadata.path)
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Directory path cannot contain forbidden characters".into(),
+        ));
+    }
+
+    if properties.home_directories {
+        let directory_path = match directory_metadata.path.as_str() {
+            "/" => format!("/{}", directory_metadata.name),
+            path => format!("{}/{}", path, directory_metadata.name),
+        };
+        if !is_in_home_directory(&directory_path, &directory_metadata.author) {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Directory must be in the home folder of its author".into(),
+            ));
+        }
+    }
+
+    if properties.exceeds_path_depth(path_depth(&directory_metadata.path) + 1) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Directory path is deeper than the maximum path depth".into(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Checks if a given name refers to the current or parent folder, which cannot name a file or folder.
+fn is_relative_name(name: &str) -> bool {
+    name == "." || name == ".."
+}
+
+/// Checks if a given path contains a component referring to the current or parent folder.
+fn has_relative_components(path: &str) -> bool {
+    path.split('/').any(is_relative_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn file_chunk(content: &[u8]) -> FileChunk {
+        FileChunk(SerializedBytes::from(UnsafeBytes::from(content.to_vec())))
+    }
+
+    #[test]
+    fn test_check_update_file_metadata() {
+        let original_file_metadata = file_metadata(&[b"hello ", b"world"]);
+        let later = Timestamp::from_micros(1);
+
+        let valid_cases = vec![
+            FileMetadata {
+                path: String::from("/moved"),
+                last_modified: later,
+                ..original_file_metadata.clone()
+            },
+            FileMetadata {
+                name: String::from("renamed.txt"),
+                last_modified: later,
+                ..original_file_metadata.clone()
+            },
+            FileMetadata {
+                last_modified: later,
+                ..file_metadata(&[b"new content"])
+            },
+        ];
+        for file_metadata in valid_cases {
+            assert_eq!(
+                check_update_file_metadata(&file_metadata, &original_file_metadata),
+                ValidateCallbackResult::Valid,
+                "Failed for input: {:?}",
+                file_metadata
+            );
+        }
+
+        let invalid_cases = vec![
+            FileMetadata {
+                created: later,
+                last_modified: later,
+                ..original_file_metadata.clone()
+            },
+            FileMetadata {
+                author: AgentPubKey::from_raw_36(vec![1; 36]),
+                last_modified: later,
+                ..original_file_metadata.clone()
+            },
+            FileMetadata {
+                name: String::from("renamed.txt"),
+                last_modified: later,
+                ..file_metadata(&[b"new content"])
+            },
+            FileMetadata {
+                name: String::from("renamed.txt"),
+                ..original_file_metadata.clone()
+            },
+        ];
+        for file_metadata in invalid_cases {
+            assert!(
+                matches!(
+                    check_update_file_metadata(&file_metadata, &original_file_metadata),
+                    ValidateCallbackResult::Invalid(_)
+                ),
+                "Failed for input: {:?}",
+                file_metadata
+            );
+        }
+    }
+
+    fn file_metadata(chunks: &[&[u8]]) -> FileMetadata {
+        let mut content_hasher = ContentHasher::default();
+        for chunk in chunks {
+            content_hasher.update(chunk);
+        }
+        let size = chunks.iter().map(|chunk| chunk.len()).sum();
+        let checksum = content_hasher.finalize();
+
+        FileMetadata {
+            name: String::from("test.txt"),
+            author: AgentPubKey::from_raw_36(vec![0; 36]),
+            path: String::from("/"),
+            created: Timestamp::from_micros(0),
+            last_modified: Timestamp::from_micros(0),
+            size,
+            stored_size: size,
+            file_type: String::from("text/plain"),
+            chunks_hashes: chunks
+                .iter()
+                .map(|_| EntryHash::from_raw_36(vec![0; 36]))
+                .collect(),
+            chunks_sizes: chunks.iter().map(|chunk| chunk.len()).collect(),
+            content_sizes: chunks.iter().map(|chunk| chunk.len()).collect(),
+            checksum: checksum.clone(),
+            content_checksum: Some(checksum),
+            encryption: None,
+            compression: None,
+        }
+    }
+
+    fn check(
+        file_metadata: &FileMetadata,
+        chunks: Vec<Option<FileChunk>>,
+    ) -> ValidateCallbackResult {
+        check_file_metadata_chunks(
+            file_metadata,
+            chunks.into_iter().map(Ok),
+            &FileSystemProperties::default(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_check_file_metadata_chunks() {
+        let chunks: Vec<Option<FileChunk>> =
+            vec![Some(file_chunk(b"hello ")), Some(file_chunk(b"world"))];
+        let valid_file_metadata = file_metadata(&[b"hello ", b"world"]);
+        assert_eq!(
+            check(&valid_file_metadata, chunks.clone()),
+            ValidateCallbackResult::Valid
+        );
+        let compressed_chunks: Vec<Vec<u8>> = [&b"hello "[..], &b"world"[..]]
+            .iter()
+            .map(|chunk| miniz_oxide::deflate::compress_to_vec(chunk, 6))
+            .collect();
+        let compressed_file_metadata = FileMetadata {
+            size: 11,
+            content_sizes: vec![6, 5],
+            content_checksum: valid_file_metadata.content_checksum.clone(),
+            compression: Some(CompressionCodec::Deflate),
+            ..file_metadata(&[&compressed_chunks[0], &compressed_chunks[1]])
+        };
+        let compressed_chunks: Vec<Option<FileChunk>> = compressed_chunks
+            .iter()
+            .map(|chunk| Some(file_chunk(chunk)))
+            .collect();
+        assert_eq!(
+            check(&compressed_file_metadata, compressed_chunks.clone()),
+            ValidateCallbackResult::Valid
+        );
+
+        let encrypted_chunk = vec![0; ENCRYPTED_CHUNK_OVERHEAD + 11];
+        let encrypted_file_metadata = FileMetadata {
+            size: 11,
+            content_sizes: Vec::new(),
+            content_checksum: None,
+            encryption: Some(FileEncryption {
+                sender: X25519PubKey::from([0; 32]),
+                wrapped_keys: Vec::new(),
+            }),
+            ..file_metadata(&[&encrypted_chunk])
+        };
+        let encrypted_chunks = vec![Some(file_chunk(&encrypted_chunk))];
+        assert_eq!(
+            check(&encrypted_file_metadata, encrypted_chunks.clone()),
+            ValidateCallbackResult::Valid
+        );
+
+        let cases: Vec<(&str, FileMetadata, Vec<Option<FileChunk>>)> = vec![
+            (
+                "wrong size",
+                FileMetadata {
+                    size: 12,
+                    ..valid_file_metadata.clone()
+                },
+                chunks.clone(),
+            ),
+            (
+                "wrong stored size",
+                FileMetadata {
+                    stored_size: 12,
+                    ..valid_file_metadata.clone()
+                },
+                chunks.clone(),
+            ),
+            (
+                "wrong chunks sizes",
+                FileMetadata {
+                    chunks_sizes: vec![5, 6],
+                    ..valid_file_metadata.clone()
+                },
+                chunks.clone(),
+            ),
+            (
+                "missing chunk size",
+                FileMetadata {
+                    chunks_sizes: vec![6],
+                    stored_size: 6,
+                    size: 6,
+                    ..valid_file_metadata.clone()
+                },
+                chunks.clone(),
+            ),
+            (
+                "wrong checksum",
+                FileMetadata {
+                    checksum: file_metadata(&[b"world", b"hello "]).checksum,
+                    ..valid_file_metadata.clone()
+                },
+                chunks.clone(),
+            ),
+            (
+                "wrong content checksum",
+                FileMetadata {
+                    content_checksum: file_metadata(&[b"world", b"hello "]).content_checksum,
+                    ..valid_file_metadata.clone()
+                },
+                chunks.clone(),
+            ),
+            (
+                "missing content checksum",
+                FileMetadata {
+                    content_checksum: None,
+                    ..valid_file_metadata.clone()
+                },
+                chunks.clone(),
+            ),
+            (
+                "missing content sizes",
+                FileMetadata {
+                    content_sizes: Vec::new(),
+                    ..valid_file_metadata.clone()
+                },
+                chunks.clone(),
+            ),
+            (
+                "wrong content sizes",
+                FileMetadata {
+                    content_sizes: vec![5, 6],
+                    ..valid_file_metadata.clone()
+                },
+                chunks.clone(),
+            ),
+            (
+                "missing content sizes of a compressed file",
+                FileMetadata {
+                    content_sizes: Vec::new(),
+                    ..compressed_file_metadata.clone()
+                },
+                compressed_chunks.clone(),
+            ),
+            (
+                "wrong content sizes of a compressed file",
+                FileMetadata {
+                    content_sizes: vec![5, 6],
+                    ..compressed_file_metadata.clone()
+                },
+                compressed_chunks.clone(),
+            ),
+            (
+                "wrong size of a compressed file",
+                FileMetadata {
+                    size: 12,
+                    content_sizes: vec![7, 5],
+                    ..compressed_file_metadata.clone()
+                },
+                compressed_chunks.clone(),
+            ),
+            (
+                "wrong content checksum of a compressed file",
+                FileMetadata {
+                    content_checksum: file_metadata(&[b"world", b"hello "]).content_checksum,
+                    ..compressed_file_metadata.clone()
+                },
+                compressed_chunks.clone(),
+            ),
+            (
+                "content checksum of an encrypted file",
+                FileMetadata {
+                    content_checksum: valid_file_metadata.content_checksum.clone(),
+                    ..encrypted_file_metadata.clone()
+                },
+                encrypted_chunks.clone(),
+            ),
+            (
+                "content sizes of an encrypted file",
+                FileMetadata {
+                    content_sizes: vec![11],
+                    ..encrypted_file_metadata.clone()
+                },
+                encrypted_chunks.clone(),
+            ),
+            (
+                "wrong size of an encrypted file",
+                FileMetadata {
+                    size: 12,
+                    ..encrypted_file_metadata.clone()
+                },
+                encrypted_chunks.clone(),
+            ),
+            (
+                "size of an encrypted compressed file larger than its chunks can hold",
+                FileMetadata {
+                    size: FileSystemProperties::default().max_chunk_size() + 1,
+                    compression: Some(CompressionCodec::Deflate),
+                    ..encrypted_file_metadata.clone()
+                },
+                encrypted_chunks.clone(),
+            ),
+            (
+                "chunk not compressed with the codec",
+                FileMetadata {
+                    compression: Some(CompressionCodec::Deflate),
+                    ..valid_file_metadata.clone()
+                },
+                chunks.clone(),
+            ),
+            (
+                "not a file chunk",
+                valid_file_metadata.clone(),
+                vec![Some(file_chunk(b"hello ")), None],
+            ),
+            (
+                "chunk too large",
+                file_metadata(&[&vec![
+                    0;
+                    FileSystemProperties::default().max_chunk_size() + 1
+                ]]),
+                vec![Some(file_chunk(&vec![
+                    0;
+                    FileSystemProperties::default()
+                        .max_chunk_size()
+                        + 1
+                ]))],
+            ),
+        ];
+
+        for (case, file_metadata, chunks) in cases {
+            assert!(
+                matches!(
+                    check(&file_metadata, chunks),
+                    ValidateCallbackResult::Invalid(_)
+                ),
+                "Failed for input: {:?}",
+                case
+            );
+        }
+    }
+
+    /// Looks up DHT data from maps, hashing paths with their name.
+    #[derive(Default)]
+    struct MockLookup {
+        actions: HashMap<ActionHash, Action>,
+        files_metadata: HashMap<ActionHash, FileMetadata>,
+    }
+
+    impl MockLookup {
+        fn create(&mut self, action_hash: &ActionHash, author: &AgentPubKey, path: &str) {
+            self.actions.insert(
+                action_hash.clone(),
+                Action::Create(Create {
+                    author: author.clone(),
+                    timestamp: Timestamp::from_micros(0),
+                    action_seq: 0,
+                    prev_action: ActionHash::from_raw_36(vec![0; 36]),
+                    entry_type: EntryType::AgentPubKey,
+                    entry_hash: EntryHash::from_raw_36(vec![0; 36]),
+                    weight: EntryRateWeight::default(),
+                }),
+            );
+            self.files_metadata.insert(
+                action_hash.clone(),
+                FileMetadata {
+                    author: author.clone(),
+                    path: String::from(path),
+                    ..file_metadata(&[])
+                },
+            );
+        }
+
+        fn update(
+            &mut self,
+            action_hash: &ActionHash,
+            original_action_hash: &ActionHash,
+            author: &AgentPubKey,
+            path: &str,
+        ) {
+            self.actions.insert(
+                action_hash.clone(),
+                Action::Update(Update {
+                    author: author.clone(),
+                    timestamp: Timestamp::from_micros(0),
+                    action_seq: 0,
+                    prev_action: ActionHash::from_raw_36(vec![0; 36]),
+                    original_action_address: original_action_hash.clone(),
+                    original_entry_address: EntryHash::from_raw_36(vec![0; 36]),
+                    entry_type: EntryType::AgentPubKey,
+                    entry_hash: EntryHash::from_raw_36(vec![0; 36]),
+                    weight: EntryRateWeight::default(),
+                }),
+            );
+            let original_file_metadata = self.files_metadata[original_action_hash].clone();
+            self.files_metadata.insert(
+                action_hash.clone(),
+                FileMetadata {
+                    author: author.clone(),
+                    path: String::from(path),
+                    ..original_file_metadata
+                },
+            );
+        }
+    }
+
+    impl DhtLookup for MockLookup {
+        fn action(&self, action_hash: ActionHash) -> ExternResult<Action> {
+            self.actions
+                .get(&action_hash)
+                .cloned()
+                .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+                    "Action not found"
+                ))))
+        }
+
+        fn file_metadata(&self, action_hash: ActionHash) -> ExternResult<Option<FileMetadata>> {
+            Ok(self.files_metadata.get(&action_hash).cloned())
+        }
+
+        fn path_hash(&self, path: &str) -> ExternResult<AnyLinkableHash> {
+            Ok(path_hash(path))
+        }
+    }
+
+    fn path_hash(path: &str) -> AnyLinkableHash {
+        let mut hash = blake2b_simd::blake2b(path.as_bytes()).as_bytes()[..32].to_vec();
+        hash.extend([0; 4]);
+        EntryHash::from_raw_36(hash).into()
+    }
+
+    fn version_tag(action_hash: &ActionHash) -> LinkTag {
+        LinkTag::new(action_hash.get_raw_39())
+    }
+
+    #[test]
+    fn test_validate_create_link_path_to_file_metadata() {
+        let alice = AgentPubKey::from_raw_36(vec![1; 36]);
+        let bob = AgentPubKey::from_raw_36(vec![2; 36]);
+        let file_hash = ActionHash::from_raw_36(vec![10; 36]);
+        let update_hash = ActionHash::from_raw_36(vec![11; 36]);
+        let other_file_hash = ActionHash::from_raw_36(vec![12; 36]);
+
+        let mut lookup = MockLookup::default();
+        lookup.create(&file_hash, &alice, "/a");
+        lookup.update(&update_hash, &file_hash, &alice, "/b");
+        lookup.create(&other_file_hash, &alice, "/c");
+
+        let validate = |base: AnyLinkableHash, tag: LinkTag, author: &AgentPubKey| {
+            validate_create_link_path_to_file_metadata(
+                base,
+                file_hash.clone().into(),
+                tag,
+                author,
+                &lookup,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(
+            validate(path_hash("/a"), LinkTag::new([]), &alice),
+            ValidateCallbackResult::Valid
+        );
+        assert_eq!(
+            validate(path_hash("/b"), version_tag(&update_hash), &alice),
+            ValidateCallbackResult::Valid
+        );
+
+        let cases = vec![
+            ("forged base", path_hash("/b"), LinkTag::new([]), &alice),
+            (
+                "forged base of version",
+                path_hash("/a"),
+                version_tag(&update_hash),
+                &alice,
+            ),
+            (
+                "forged tag of another file",
+                path_hash("/c"),
+                version_tag(&other_file_hash),
+                &alice,
+            ),
+            (
+                "malformed tag",
+                path_hash("/a"),
+                LinkTag::new([1, 2, 3]),
+                &alice,
+            ),
+            ("non-author", path_hash("/a"), LinkTag::new([]), &bob),
+        ];
+
+        for (case, base, tag, author) in cases {
+            assert!(
+                matches!(
+                    validate(base, tag, author),
+                    ValidateCallbackResult::Invalid(_)
+                ),
+                "Failed for input: {:?}",
+                case
+            );
+        }
+
+        assert!(matches!(
+            validate_create_link_path_to_file_metadata(
+                path_hash("/a"),
+                EntryHash::from_raw_36(vec![10; 36]).into(),
+                LinkTag::new([]),
+                &alice,
+                &lookup,
+            )
+            .unwrap(),
+            ValidateCallbackResult::Invalid(_)
+        ));
+    }
+
+    #[test]
+    fn test_validate_create_link_file_metadata_update() {
+        let alice = AgentPubKey::from_raw_36(vec![1; 36]);
+        let bob = AgentPubKey::from_raw_36(vec![2; 36]);
+        let file_hash = ActionHash::from_raw_36(vec![10; 36]);
+        let update_hash = ActionHash::from_raw_36(vec![11; 36]);
+        let other_file_hash = ActionHash::from_raw_36(vec![12; 36]);
+        let bob_update_hash = ActionHash::from_raw_36(vec![13; 36]);
+
+        let mut lookup = MockLookup::default();
+        lookup.create(&file_hash, &alice, "/a");
+        lookup.update(&update_hash, &file_hash, &alice, "/b");
+        lookup.create(&other_file_hash, &alice, "/c");
+        lookup.update(&bob_update_hash, &file_hash, &bob, "/d");
+
+        let validate = |base: &ActionHash, target: &ActionHash, author: &AgentPubKey| {
+            validate_create_link_file_metadata_update(
+                base.clone().into(),
+                target.clone().into(),
+                author,
+                &lookup,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(
+            validate(&file_hash, &update_hash, &alice),
+            ValidateCallbackResult::Valid
+        );
+
+        let cases = vec![
+            ("forged base", &other_file_hash, &update_hash, &alice),
+            ("not an update", &file_hash, &file_hash, &alice),
+            ("non-author", &file_hash, &update_hash, &bob),
+            (
+                "update by another agent",
+                &file_hash,
+                &bob_update_hash,
+                &alice,
+            ),
+        ];
+
+        for (case, base, target, author) in cases {
+            assert!(
+                matches!(
+                    validate(base, target, author),
+                    ValidateCallbackResult::Invalid(_)
+                ),
+                "Failed for input: {:?}",
+                case
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_create_link_file_chunk_to_file_metadata() {
+        let alice = AgentPubKey::from_raw_36(vec![1; 36]);
+        let bob = AgentPubKey::from_raw_36(vec![2; 36]);
+        let file_hash = ActionHash::from_raw_36(vec![10; 36]);
+        let chunk_hash = EntryHash::from_raw_36(vec![20; 36]);
+        let other_chunk_hash = EntryHash::from_raw_36(vec![21; 36]);
+
+        let mut lookup = MockLookup::default();
+        lookup.create(&file_hash, &alice, "/a");
+        lookup
+            .files_metadata
+            .get_mut(&file_hash)
+            .unwrap()
+            .chunks_hashes = vec![chunk_hash.clone()];
+
+        let validate = |base: AnyLinkableHash, target: AnyLinkableHash, author: &AgentPubKey| {
+            validate_create_link_file_chunk_to_file_metadata(base, target, author, &lookup).unwrap()
+        };
+
+        assert_eq!(
+            validate(chunk_hash.clone().into(), file_hash.clone().into(), &alice),
+            ValidateCallbackResult::Valid
+        );
+
+        let cases: Vec<(&str, AnyLinkableHash, AnyLinkableHash, &AgentPubKey)> = vec![
+            (
+                "chunk not listed",
+                other_chunk_hash.into(),
+                file_hash.clone().into(),
+                &alice,
+            ),
+            (
+                "non-author",
+                chunk_hash.clone().into(),
+                file_hash.clone().into(),
+                &bob,
+            ),
+            (
+                "not a file metadata action",
+                chunk_hash.clone().into(),
+                chunk_hash.clone().into(),
+                &alice,
+            ),
+        ];
+
+        for (case, base, target, author) in cases {
+            assert!(
+                matches!(
+                    validate(base, target, author),
+                    ValidateCallbackResult::Invalid(_)
+                ),
+                "Failed for input: {:?}",
+                case
+            );
+        }
+    }
 }
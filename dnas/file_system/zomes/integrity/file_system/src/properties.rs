@@ -0,0 +1,342 @@
+//! This module defines the limits and naming rules of the file system, which each deployment of the DNA can set
+//! in its properties.
+
+use hdi::prelude::*;
+use unicode_normalization::UnicodeNormalization;
+
+/// Smallest average chunk size that can be configured.
+pub const MIN_AVERAGE_CHUNK_SIZE: usize = 64;
+
+/// Largest average chunk size that can be configured, so that the largest chunks still fit in a 4 MB entry.
+pub const MAX_AVERAGE_CHUNK_SIZE: usize = 1_900_000;
+
+/// Limits and naming rules of the file system, read from the DNA properties. Missing properties take their default value.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct FileSystemProperties {
+    /// Average size of the content-defined chunks, in bytes. Chunks are between a quarter and twice this size.
+    pub average_chunk_size: usize,
+    /// Maximum size of a file, in bytes. Unlimited if not set.
+    pub max_file_size: Option<usize>,
+    /// Maximum number of folders a file or folder can be nested in. Unlimited if not set.
+    pub max_path_depth: Option<usize>,
+    /// Maximum number of characters of a file or folder name. Unlimited if not set.
+    pub max_name_length: Option<usize>,
+    /// Characters that cannot be used in file and folder paths.
+    pub forbidden_chars: String,
+    /// Maximum number of files each agent can store. Unlimited if not set.
+    pub max_files_per_agent: Option<usize>,
+    /// Maximum number of bytes each agent can store, counting each distinct chunk of their files once.
+    /// Unlimited if not set.
+    pub max_bytes_per_agent: Option<usize>,
+    /// Whether file and folder names differing only by their case refer to the same file or folder.
+    pub case_insensitive: bool,
+    /// Whether each agent gets their own home folder, `/<agent>`, and can only store files and folders in it.
+    pub home_directories: bool,
+}
+
+impl Default for FileSystemProperties {
+    fn default() -> Self {
+        FileSystemProperties {
+            average_chunk_size: 1024 * 1024, // 1 MB
+            max_file_size: None,
+            max_path_depth: None,
+            max_name_length: None,
+            forbidden_chars: String::from("<>:\"|?*"),
+            max_files_per_agent: None,
+            max_bytes_per_agent: None,
+            case_insensitive: false,
+            home_directories: false,
+        }
+    }
+}
+
+/// Wrapper to deserialize DNA properties that may be left empty.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug)]
+struct DnaProperties(Option<FileSystemProperties>);
+
+impl FileSystemProperties {
+    /// Smallest size of a content-defined chunk, except for the last chunk of a file.
+    pub fn min_chunk_size(&self) -> usize {
+        self.average_chunk_size / 4
+    }
+
+    /// Largest size of a chunk.
+    pub fn max_chunk_size(&self) -> usize {
+        self.average_chunk_size * 2
+    }
+
+    /// Returns the key under which a file or folder name is looked up and must be unique in its folder:
+    /// the name itself, or its lowercase form in case-insensitive mode.
+    pub fn name_key(&self, name: &str) -> String {
+        let name = normalize_name(name);
+        if self.case_insensitive {
+            name.to_lowercase()
+        } else {
+            name
+        }
+    }
+
+    /// Checks if a given path contains any forbidden characters, returning `true` if any are found.
+    pub fn has_forbidden_chars(&self, path: &str) -> bool {
+        path.chars().any(|c| self.forbidden_chars.contains(c))
+    }
+
+    /// Checks if a given file size is larger than the maximum file size.
+    pub fn exceeds_file_size(&self, size: usize) -> bool {
+        self.max_file_size
+            .map_or(false, |max_file_size| size > max_file_size)
+    }
+
+    /// Checks if a given name is longer than the maximum name length.
+    pub fn exceeds_name_length(&self, name: &str) -> bool {
+        self.max_name_length.map_or(false, |max_name_length| {
+            name.chars().count() > max_name_length
+        })
+    }
+
+    /// Checks if any folder of a given path has a name longer than the maximum name length.
+    pub fn exceeds_path_name_length(&self, path: &str) -> bool {
+        path.split('/').any(|part| self.exceeds_name_length(part))
+    }
+
+    /// Checks if a given number of nested folders is deeper than the maximum path depth.
+    pub fn exceeds_path_depth(&self, depth: usize) -> bool {
+        self.max_path_depth
+            .map_or(false, |max_path_depth| depth > max_path_depth)
+    }
+}
+
+/// Reads the file system limits and naming rules from the DNA properties.
+pub fn file_system_properties() -> ExternResult<FileSystemProperties> {
+    let properties = DnaProperties::try_from(dna_info()?.properties).map_err(|e| wasm_error!(e))?;
+
+    let properties = properties.0.unwrap_or_default();
+    check_average_chunk_size(&properties)?;
+
+    Ok(properties)
+}
+
+/// Fails if the average chunk size of the properties is out of the range that can be configured.
+fn check_average_chunk_size(properties: &FileSystemProperties) -> ExternResult<()> {
+    if !(MIN_AVERAGE_CHUNK_SIZE..=MAX_AVERAGE_CHUNK_SIZE).contains(&properties.average_chunk_size) {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "The average chunk size must be between {} and {} bytes",
+            MIN_AVERAGE_CHUNK_SIZE, MAX_AVERAGE_CHUNK_SIZE
+        ))));
+    }
+
+    Ok(())
+}
+
+/// Returns the number of folders in a standardized file system path.
+pub fn path_depth(path: &str) -> usize {
+    path.split('/').filter(|part| !part.is_empty()).count()
+}
+
+/// Returns the name of the home folder of an agent: the hexadecimal encoding of their public key.
+pub fn home_directory_name(agent: &AgentPubKey) -> String {
+    agent
+        .get_raw_39()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Returns the standardized path of the home folder of an agent.
+pub fn home_directory_path(agent: &AgentPubKey) -> String {
+    format!("/{}", home_directory_name(agent))
+}
+
+/// Checks if a given standardized path is the home folder of an agent, or is nested in it.
+pub fn is_in_home_directory(path: &str, agent: &AgentPubKey) -> bool {
+    let home_directory_path = home_directory_path(agent);
+    path == home_directory_path
+        || path
+            .strip_prefix(&home_directory_path)
+            .map_or(false, |rest| rest.starts_with('/'))
+}
+
+/// Returns the Unicode NFC normalization of a file or folder name or path, so that names typed with composed or
+/// decomposed characters refer to the same file.
+pub fn normalize_name(name: &str) -> String {
+    name.nfc().collect()
+}
+
+/// Checks if a given file or folder name or path is in Unicode NFC normalization form.
+pub fn is_normalized_name(name: &str) -> bool {
+    unicode_normalization::is_nfc(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limited_properties() -> FileSystemProperties {
+        FileSystemProperties {
+            max_file_size: Some(10),
+            max_path_depth: Some(2),
+            max_name_length: Some(5),
+            ..FileSystemProperties::default()
+        }
+    }
+
+    #[test]
+    fn test_exceeds_limits() {
+        let properties = limited_properties();
+        let cases = vec![
+            ("file size below", properties.exceeds_file_size(9), false),
+            ("file size at", properties.exceeds_file_size(10), false),
+            ("file size above", properties.exceeds_file_size(11), true),
+            ("name below", properties.exceeds_name_length("abcd"), false),
+            ("name at", properties.exceeds_name_length("abcde"), false),
+            ("name above", properties.exceeds_name_length("abcdef"), true),
+            (
+                "name characters",
+                properties.exceeds_name_length("ééééé"),
+                false,
+            ),
+            (
+                "path names",
+                properties.exceeds_path_name_length("/abcde/ab"),
+                false,
+            ),
+            (
+                "path name above",
+                properties.exceeds_path_name_length("/ab/abcdef"),
+                true,
+            ),
+            ("root path", properties.exceeds_path_name_length("/"), false),
+            ("depth at", properties.exceeds_path_depth(2), false),
+            ("depth above", properties.exceeds_path_depth(3), true),
+        ];
+
+        for (case, result, expected) in cases {
+            assert_eq!(result, expected, "Failed for input: {:?}", case);
+        }
+
+        let properties = FileSystemProperties::default();
+        assert!(!properties.exceeds_file_size(usize::MAX));
+        assert!(!properties.exceeds_name_length(&"a".repeat(1000)));
+        assert!(!properties.exceeds_path_name_length(&format!("/{}", "a".repeat(1000))));
+        assert!(!properties.exceeds_path_depth(1000));
+    }
+
+    #[test]
+    fn test_has_forbidden_chars() {
+        let properties = FileSystemProperties::default();
+        let cases = vec![
+            ("/a/b.txt", false),
+            ("/a b/c-d_e", false),
+            ("/a<b", true),
+            ("/a>b", true),
+            ("/a:b", true),
+            ("/a\"b", true),
+            ("/a|b", true),
+            ("/a?b", true),
+            ("/a*b", true),
+        ];
+
+        for (path, expected) in cases {
+            assert_eq!(
+                properties.has_forbidden_chars(path),
+                expected,
+                "Failed for input: {:?}",
+                path
+            );
+        }
+
+        let properties = FileSystemProperties {
+            forbidden_chars: String::from("#"),
+            ..FileSystemProperties::default()
+        };
+        assert!(properties.has_forbidden_chars("/a#b"));
+        assert!(!properties.has_forbidden_chars("/a?b"));
+    }
+
+    #[test]
+    fn test_name_key() {
+        let properties = FileSystemProperties::default();
+        let cases = vec![
+            ("Notes.TXT", "Notes.TXT"),
+            ("e\u{301}t\u{e9}", "\u{e9}t\u{e9}"),
+        ];
+
+        for (name, expected) in cases {
+            assert_eq!(
+                properties.name_key(name),
+                expected,
+                "Failed for input: {:?}",
+                name
+            );
+        }
+
+        let properties = FileSystemProperties {
+            case_insensitive: true,
+            ..FileSystemProperties::default()
+        };
+        let cases = vec![
+            ("Notes.TXT", "notes.txt"),
+            ("E\u{301}T\u{c9}", "\u{e9}t\u{e9}"),
+        ];
+
+        for (name, expected) in cases {
+            assert_eq!(
+                properties.name_key(name),
+                expected,
+                "Failed for input: {:?}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_in_home_directory() {
+        let agent = AgentPubKey::from_raw_36(vec![1; 36]);
+        let other_agent = AgentPubKey::from_raw_36(vec![2; 36]);
+        let home = home_directory_path(&agent);
+        let cases = vec![
+            (home.clone(), &agent, true),
+            (format!("{}/docs", home), &agent, true),
+            (format!("{}/docs/notes", home), &agent, true),
+            (format!("{}docs", home), &agent, false),
+            (String::from("/"), &agent, false),
+            (String::from("/docs"), &agent, false),
+            (home.clone(), &other_agent, false),
+            (format!("{}/docs", home), &other_agent, false),
+        ];
+
+        for (path, agent, expected) in cases {
+            assert_eq!(
+                is_in_home_directory(&path, agent),
+                expected,
+                "Failed for input: {:?}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_average_chunk_size() {
+        let cases = vec![
+            (MIN_AVERAGE_CHUNK_SIZE - 1, false),
+            (MIN_AVERAGE_CHUNK_SIZE, true),
+            (1024 * 1024, true),
+            (MAX_AVERAGE_CHUNK_SIZE, true),
+            (MAX_AVERAGE_CHUNK_SIZE + 1, false),
+        ];
+
+        for (average_chunk_size, expected) in cases {
+            let properties = FileSystemProperties {
+                average_chunk_size,
+                ..FileSystemProperties::default()
+            };
+            assert_eq!(
+                check_average_chunk_size(&properties).is_ok(),
+                expected,
+                "Failed for input: {:?}",
+                average_chunk_size
+            );
+        }
+    }
+}
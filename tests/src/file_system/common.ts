@@ -8,8 +8,81 @@ export type FileMetadata = {
 	created: number,
 	last_modified: number,
 	size: number,
+	stored_size: number,
 	file_type: string,
 	chunks_hashes: Uint8Array[],
+	chunks_sizes: number[],
+	content_sizes: number[],
+	checksum: string,
+	content_checksum: string | null,
+	encryption: FileEncryption | null,
+	compression: CompressionCodec | null,
+}
+
+export type CompressionCodec = "Deflate";
+
+export type FileEncryption = {
+	sender: Uint8Array,
+	wrapped_keys: WrappedFileKey[],
+}
+
+export type WrappedFileKey = {
+	agent: AgentPubKey,
+	recipient: Uint8Array,
+	wrapped_key: unknown,
+}
+
+export type FileVerification = {
+	checksum: string,
+	computed_checksum: string,
+	content_checksum: string | null,
+	computed_content_checksum: string | null,
+	valid: boolean,
+}
+
+export type FileChunkOutput = {
+	file_chunk: Record,
+	chunks_count: number,
+}
+
+export type GarbageCollectionReport = {
+	reclaimed_chunks: number,
+	reclaimed_bytes: number,
+	delete_actions: ActionHash[],
+}
+
+export type StorageUsage = {
+	files: number,
+	bytes: number,
+	max_files: number | null,
+	max_bytes: number | null,
+}
+
+export type SharePermission = "Read" | "Write";
+
+export type SharedFile = {
+	owner: AgentPubKey,
+	permission: SharePermission,
+	file_metadata: Record,
+}
+
+export type DirectoryMetadata = {
+	name: string,
+	author: AgentPubKey,
+	path: string,
+	created: number,
+	last_modified: number,
+}
+
+export type DirectoryOutput = {
+	name: string,
+	path: string,
+	directory_metadata: Record | null,
+}
+
+export type DirectoryContent = {
+	files: Record[],
+	directories: DirectoryOutput[],
 }
 
 export type CreateFileInput = {
@@ -17,6 +90,9 @@ export type CreateFileInput = {
 	path: string,
 	file_type: string,
 	content: Uint8Array,
+	encrypted_for?: AgentPubKey[],
+	compression?: CompressionCodec,
+	private?: boolean,
 }
 
 export type FileOutput = {
@@ -32,6 +108,46 @@ export async function createFile(cell: CallableCell, file: CreateFileInput): Pro
 	});
 }
 
+export async function beginUpload(cell: CallableCell, path: string, name: string, file_type: string = "text/plain"): Promise<ActionHash> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "begin_upload",
+		payload: {
+			name,
+			path,
+			file_type,
+		},
+	});
+}
+
+export async function uploadChunk(cell: CallableCell, session: ActionHash, index: number, bytes: Uint8Array): Promise<Record> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "upload_chunk",
+		payload: {
+			session,
+			index,
+			bytes,
+		},
+	});
+}
+
+export async function finishUpload(cell: CallableCell, session: ActionHash): Promise<Record> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "finish_upload",
+		payload: session,
+	});
+}
+
+export async function cancelUpload(cell: CallableCell, session: ActionHash): Promise<void> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "cancel_upload",
+		payload: session,
+	});
+}
+
 export async function getFileChunks(cell: CallableCell, file_metadata_hash: ActionHash): Promise<Record[]> {
 	return cell.callZome({
 		zome_name: "file_system",
@@ -40,6 +156,37 @@ export async function getFileChunks(cell: CallableCell, file_metadata_hash: Acti
 	});
 }
 
+export async function getFileChunkByIndex(cell: CallableCell, original_file_metadata_hash: ActionHash, index: number): Promise<FileChunkOutput> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "get_file_chunk_by_index",
+		payload: {
+			original_file_metadata_hash,
+			index,
+		},
+	});
+}
+
+export async function readFileRange(cell: CallableCell, original_file_metadata_hash: ActionHash, offset: number, length: number): Promise<Uint8Array> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "read_file_range",
+		payload: {
+			original_file_metadata_hash,
+			offset,
+			length,
+		},
+	});
+}
+
+export async function verifyFile(cell: CallableCell, original_file_metadata_hash: ActionHash): Promise<FileVerification> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "verify_file",
+		payload: original_file_metadata_hash,
+	});
+}
+
 export async function getFileMetadata(cell: CallableCell, file_metadata_hash: ActionHash): Promise<Record | null> {
 	return cell.callZome({
 		zome_name: "file_system",
@@ -67,6 +214,122 @@ export async function updateFile(cell: CallableCell, original_file_metadata_hash
 	});
 }
 
+export async function shareFile(cell: CallableCell, original_file_metadata_hash: ActionHash, agent: AgentPubKey, permission: SharePermission): Promise<ActionHash> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "share_file",
+		payload: {
+			original_file_metadata_hash,
+			agent,
+			permission,
+		},
+	});
+}
+
+export async function unshareFile(cell: CallableCell, original_file_metadata_hash: ActionHash, agent: AgentPubKey): Promise<void> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "unshare_file",
+		payload: {
+			original_file_metadata_hash,
+			agent,
+		},
+	});
+}
+
+export async function listSharedWithMe(cell: CallableCell): Promise<SharedFile[]> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "list_shared_with_me",
+		payload: null,
+	});
+}
+
+export async function getSharedFileMetadata(cell: CallableCell, owner: AgentPubKey, original_file_metadata_hash: ActionHash): Promise<Record | null> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "get_shared_file_metadata",
+		payload: {
+			owner,
+			original_file_metadata_hash,
+		},
+	});
+}
+
+export async function getSharedFileChunks(cell: CallableCell, owner: AgentPubKey, original_file_metadata_hash: ActionHash): Promise<Record[]> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "get_shared_file_chunks",
+		payload: {
+			owner,
+			original_file_metadata_hash,
+		},
+	});
+}
+
+export async function updateSharedFile(cell: CallableCell, owner: AgentPubKey, original_file_metadata_hash: ActionHash, new_content: Uint8Array): Promise<FileOutput> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "update_shared_file",
+		payload: {
+			owner,
+			original_file_metadata_hash,
+			new_content,
+		},
+	});
+}
+
+export async function getEncryptionKey(cell: CallableCell, agent: AgentPubKey): Promise<Uint8Array | null> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "get_encryption_key",
+		payload: agent,
+	});
+}
+
+export async function getFileVersions(cell: CallableCell, original_file_metadata_hash: ActionHash): Promise<Record[]> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "get_file_versions",
+		payload: original_file_metadata_hash,
+	});
+}
+
+export async function restoreFileVersion(cell: CallableCell, original_file_metadata_hash: ActionHash, version_file_metadata_hash: ActionHash): Promise<Record> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "restore_file_version",
+		payload: {
+			original_file_metadata_hash,
+			version_file_metadata_hash,
+		},
+	});
+}
+
+export async function moveFile(cell: CallableCell, original_file_metadata_hash: ActionHash, new_path: string | null, new_name: string | null = null): Promise<Record> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "move_file",
+		payload: {
+			original_file_metadata_hash,
+			new_path,
+			new_name,
+		},
+	});
+}
+
+export async function copyFile(cell: CallableCell, original_file_metadata_hash: ActionHash, new_path: string, new_name: string | null = null): Promise<Record> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "copy_file",
+		payload: {
+			original_file_metadata_hash,
+			new_path,
+			new_name,
+		},
+	});
+}
+
 export async function deleteFile(cell: CallableCell, original_file_metadata_hash: ActionHash): Promise<Record> {
 	return cell.callZome({
 		zome_name: "file_system",
@@ -75,6 +338,111 @@ export async function deleteFile(cell: CallableCell, original_file_metadata_hash
 	});
 }
 
+export async function trashFile(cell: CallableCell, original_file_metadata_hash: ActionHash): Promise<Record> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "trash_file",
+		payload: original_file_metadata_hash,
+	});
+}
+
+export async function listTrash(cell: CallableCell): Promise<Record[]> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "list_trash",
+		payload: null,
+	});
+}
+
+export async function restoreFromTrash(cell: CallableCell, original_file_metadata_hash: ActionHash): Promise<Record> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "restore_from_trash",
+		payload: original_file_metadata_hash,
+	});
+}
+
+export async function emptyTrash(cell: CallableCell): Promise<ActionHash[]> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "empty_trash",
+		payload: null,
+	});
+}
+
+export async function collectGarbage(cell: CallableCell): Promise<GarbageCollectionReport> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "collect_garbage",
+		payload: null,
+	});
+}
+
+export async function getMyUsage(cell: CallableCell): Promise<StorageUsage> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "get_my_usage",
+		payload: null,
+	});
+}
+
+export async function createDirectory(cell: CallableCell, path: string, name: string): Promise<Record> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "create_directory",
+		payload: {
+			name,
+			path,
+		},
+	});
+}
+
+export async function listDirectory(cell: CallableCell, path: string): Promise<DirectoryContent> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "list_directory",
+		payload: path,
+	});
+}
+
+export async function getHomeDirectory(cell: CallableCell, agent: AgentPubKey): Promise<string> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "get_home_directory",
+		payload: agent,
+	});
+}
+
+export async function resolvePath(cell: CallableCell, path: string): Promise<string> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "resolve_path",
+		payload: path,
+	});
+}
+
+export async function listHomeDirectory(cell: CallableCell, agent: AgentPubKey, path: string): Promise<DirectoryContent> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "list_home_directory",
+		payload: {
+			agent,
+			path,
+		},
+	});
+}
+
+export async function deleteDirectory(cell: CallableCell, path: string, recursive: boolean = false): Promise<ActionHash[]> {
+	return cell.callZome({
+		zome_name: "file_system",
+		fn_name: "delete_directory",
+		payload: {
+			path,
+			recursive,
+		},
+	});
+}
+
 export function sampleFileInput(
 	path: string = "/",
 	name: string = "test.txt",
@@ -2,17 +2,50 @@ import { assert, expect, test } from "vitest";
 import { runScenario, pause, Scenario, Player } from "@holochain/tryorama";
 import { Record } from "@holochain/client";
 import { decode } from "@msgpack/msgpack";
+import { readFileSync } from "fs";
+import { gunzipSync } from "zlib";
 
 import {
+  beginUpload,
+  cancelUpload,
+  collectGarbage,
+  copyFile,
+  createDirectory,
   createFile,
+  deleteDirectory,
   deleteFile,
+  DirectoryMetadata,
+  emptyTrash,
   FileMetadata,
+  finishUpload,
   fiveMbFileInput,
+  getFileChunkByIndex,
   getFileChunks,
   getFileMetadata,
   getFilesMetadataByPathRecursively,
+  getFileVersions,
+  getMyUsage,
+  getHomeDirectory,
+  resolvePath,
+  listHomeDirectory,
+  shareFile,
+  unshareFile,
+  listSharedWithMe,
+  getSharedFileMetadata,
+  getSharedFileChunks,
+  updateSharedFile,
+  getEncryptionKey,
+  listDirectory,
+  listTrash,
+  moveFile,
+  readFileRange,
+  restoreFileVersion,
+  restoreFromTrash,
   sampleFileInput,
+  trashFile,
   updateFile,
+  uploadChunk,
+  verifyFile,
 } from "./common";
 
 function decodeOutputs(records: Record[]): unknown[] {
@@ -22,14 +55,21 @@ function decodeOutputs(records: Record[]): unknown[] {
 const hAppPath = process.cwd() + "/../workdir/soushi-cloud.happ";
 const appSource = { appBundleSource: { path: hAppPath } };
 
+// Returns an app source whose DNA is installed with the given properties, by editing the manifest of the hApp bundle
+function appSourceWithProperties(properties: object) {
+  const bundle = decode(gunzipSync(readFileSync(hAppPath))) as any;
+  bundle.manifest.roles[0].dna.modifiers.properties = properties;
+
+  return { appBundleSource: { bundle } };
+}
+
 async function runScenarioWithTwoAgents(
-  callback: (scenario: Scenario, alice: Player, bob: Player) => Promise<void>
+  callback: (scenario: Scenario, alice: Player, bob: Player) => Promise<void>,
+  properties?: object
 ) {
+  const source = properties ? appSourceWithProperties(properties) : appSource;
   await runScenario(async (scenario) => {
-    const [alice, bob] = await scenario.addPlayersWithApps([
-      appSource,
-      appSource,
-    ]);
+    const [alice, bob] = await scenario.addPlayersWithApps([source, source]);
     await scenario.shareAllAgents();
 
     await callback(scenario, alice, bob);
@@ -88,7 +128,17 @@ test("create large file and delete it", async () => {
       alice.cells[0],
       fiveMbFileInput("/", "large_file.txt")
     );
-    assert.equal(records.file_chunks.length, 5);
+    const decodedMetadata = decodeOutputs([
+      records.file_metadata,
+    ])[0] as FileMetadata;
+    assert.equal(
+      records.file_chunks.length,
+      decodedMetadata.chunks_hashes.length
+    );
+    assert.equal(
+      decodedMetadata.chunks_sizes.reduce((a, b) => a + b, 0),
+      5 * 1024 * 1024
+    );
 
     await pause(1200);
 
@@ -251,6 +301,1195 @@ test("create file with empty name and check path standardization", async () => {
   });
 });
 
+test("create directories, list them and delete them", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    // Create an empty folder and a folder containing files
+    const emptyFolder = await createDirectory(alice.cells[0], "/", "empty");
+    assert.ok(emptyFolder);
+    await createDirectory(alice.cells[0], "/", "documents");
+    await createFile(alice.cells[0], sampleFileInput("/documents"));
+    await createFile(alice.cells[0], sampleFileInput("/documents/archive"));
+    await createFile(alice.cells[0], sampleFileInput("/"));
+
+    // Try to create the same folder twice (should fail)
+    await expect(
+      createDirectory(alice.cells[0], "/", "empty")
+    ).rejects.toThrow();
+
+    await pause(1200);
+
+    // List the root folder without recursing
+    let content = await listDirectory(bob.cells[0], "/");
+    assert.equal(content.files.length, 1);
+    assert.equal(content.directories.length, 2);
+    const names = content.directories.map((d) => d.name).sort();
+    assert.deepEqual(names, ["documents", "empty"]);
+
+    const decodedFolder = decodeOutputs([
+      content.directories.find((d) => d.name === "empty")!.directory_metadata!,
+    ])[0] as DirectoryMetadata;
+    assert.equal(decodedFolder.path, "/");
+
+    // Folders created implicitly by files have no metadata
+    content = await listDirectory(bob.cells[0], "/documents");
+    assert.equal(content.files.length, 1);
+    assert.equal(content.directories.length, 1);
+    assert.equal(content.directories[0].path, "/documents/archive");
+    assert.isNull(content.directories[0].directory_metadata);
+
+    // Non-recursive deletion of a non-empty folder (should fail)
+    await expect(
+      deleteDirectory(alice.cells[0], "/documents")
+    ).rejects.toThrow();
+
+    // Delete the empty folder and the non-empty one recursively
+    await deleteDirectory(alice.cells[0], "/empty");
+    await deleteDirectory(alice.cells[0], "/documents", true);
+
+    await pause(1200);
+
+    content = await listDirectory(bob.cells[0], "/");
+    assert.equal(content.files.length, 1);
+    assert.equal(content.directories.length, 0);
+
+    const readOutput = await getFilesMetadataByPathRecursively(
+      bob.cells[0],
+      "/"
+    );
+    assert.equal(readOutput.length, 1);
+  });
+});
+
+test("move and rename files across directories", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    const records = await createFile(alice.cells[0], sampleFileInput("/a"));
+    const original_action_hash =
+      records.file_metadata.signed_action.hashed.hash;
+    await createFile(alice.cells[0], sampleFileInput("/b", "taken.txt"));
+
+    await pause(1200);
+
+    // Move the file to another folder
+    const moved = await moveFile(alice.cells[0], original_action_hash, "/b");
+    let decoded = decodeOutputs([moved])[0] as FileMetadata;
+    assert.equal(decoded.path, "/b");
+    assert.equal(decoded.name, "test.txt");
+
+    // Moving onto an existing file (should fail)
+    await expect(
+      moveFile(alice.cells[0], original_action_hash, null, "taken.txt")
+    ).rejects.toThrow();
+
+    // Rename the file in place
+    const renamed = await moveFile(
+      alice.cells[0],
+      original_action_hash,
+      null,
+      "renamed.txt"
+    );
+    decoded = decodeOutputs([renamed])[0] as FileMetadata;
+    assert.equal(decoded.path, "/b");
+    assert.equal(decoded.name, "renamed.txt");
+
+    await pause(1200);
+
+    let readOutput = await getFilesMetadataByPathRecursively(bob.cells[0], "/a");
+    assert.equal(readOutput.length, 0);
+    readOutput = await getFilesMetadataByPathRecursively(bob.cells[0], "/b");
+    assert.equal(readOutput.length, 2);
+
+    // The content is still reachable from the original hash
+    const chunksRecords = await getFileChunks(
+      bob.cells[0],
+      original_action_hash
+    );
+    assert.equal(
+      new TextDecoder().decode(decodeOutputs(chunksRecords)[0] as Uint8Array),
+      "hello world !"
+    );
+  });
+});
+
+test("copy a file without re-uploading its content", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    const records = await createFile(
+      alice.cells[0],
+      fiveMbFileInput("/", "large_file.txt")
+    );
+    const original = decodeOutputs([records.file_metadata])[0] as FileMetadata;
+
+    await pause(1200);
+
+    // Bob copies Alice's file into his own folder
+    const copy = await copyFile(
+      bob.cells[0],
+      records.file_metadata.signed_action.hashed.hash,
+      "/copies",
+      "copy.txt"
+    );
+    const decoded = decodeOutputs([copy])[0] as FileMetadata;
+    assert.equal(decoded.path, "/copies");
+    assert.equal(decoded.name, "copy.txt");
+    assert.equal(decoded.size, original.size);
+    assert.deepEqual(decoded.author, bob.agentPubKey);
+    assert.deepEqual(decoded.chunks_hashes, original.chunks_hashes);
+
+    // Copying onto an existing file (should fail)
+    await expect(
+      copyFile(
+        bob.cells[0],
+        records.file_metadata.signed_action.hashed.hash,
+        "/copies",
+        "copy.txt"
+      )
+    ).rejects.toThrow();
+
+    await pause(1200);
+
+    const chunksRecords = await getFileChunks(
+      alice.cells[0],
+      copy.signed_action.hashed.hash
+    );
+    assert.equal(chunksRecords.length, original.chunks_hashes.length);
+  });
+});
+
+test("list file versions and restore a previous one", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    const records = await createFile(alice.cells[0], sampleFileInput());
+    const original_action_hash =
+      records.file_metadata.signed_action.hashed.hash;
+
+    await pause(1200);
+
+    await updateFile(
+      alice.cells[0],
+      original_action_hash,
+      new TextEncoder().encode("second version")
+    );
+    await updateFile(
+      alice.cells[0],
+      original_action_hash,
+      new TextEncoder().encode("third version !")
+    );
+
+    await pause(1200);
+
+    // Every version is returned, from the oldest to the newest
+    const versions = await getFileVersions(bob.cells[0], original_action_hash);
+    assert.equal(versions.length, 3);
+    const decodedVersions = decodeOutputs(versions) as FileMetadata[];
+    assert.deepEqual(
+      decodedVersions.map((v) => v.size),
+      [13, 14, 15]
+    );
+    assert.deepEqual(versions[0].signed_action.hashed.hash, original_action_hash);
+
+    // Restore the original content
+    await restoreFileVersion(
+      alice.cells[0],
+      original_action_hash,
+      versions[0].signed_action.hashed.hash
+    );
+
+    await pause(1200);
+
+    const chunksRecords = await getFileChunks(bob.cells[0], original_action_hash);
+    assert.equal(
+      new TextDecoder().decode(decodeOutputs(chunksRecords)[0] as Uint8Array),
+      "hello world !"
+    );
+
+    const newVersions = await getFileVersions(
+      bob.cells[0],
+      original_action_hash
+    );
+    assert.equal(newVersions.length, 4);
+  });
+});
+
+test("trash files, restore them and empty the trash", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    const first = await createFile(alice.cells[0], sampleFileInput("/docs"));
+    const second = await createFile(
+      alice.cells[0],
+      sampleFileInput("/docs", "other.txt")
+    );
+    const first_hash = first.file_metadata.signed_action.hashed.hash;
+    const second_hash = second.file_metadata.signed_action.hashed.hash;
+
+    await pause(1200);
+
+    await trashFile(alice.cells[0], first_hash);
+    await trashFile(alice.cells[0], second_hash);
+
+    await pause(1200);
+
+    // Trashed files are no longer in the file system tree
+    let readOutput = await getFilesMetadataByPathRecursively(bob.cells[0], "/");
+    assert.equal(readOutput.length, 0);
+
+    let trash = await listTrash(alice.cells[0]);
+    assert.equal(trash.length, 2);
+
+    // Bob has his own, empty, trash
+    const bobTrash = await listTrash(bob.cells[0]);
+    assert.equal(bobTrash.length, 0);
+
+    // Restore a file at its original path
+    await restoreFromTrash(alice.cells[0], first_hash);
+
+    await pause(1200);
+
+    readOutput = await getFilesMetadataByPathRecursively(bob.cells[0], "/docs");
+    assert.equal(readOutput.length, 1);
+    assert.equal(
+      (decodeOutputs(readOutput)[0] as FileMetadata).name,
+      "test.txt"
+    );
+
+    // Empty the trash, permanently deleting the remaining file
+    await emptyTrash(alice.cells[0]);
+
+    await pause(1200);
+
+    trash = await listTrash(alice.cells[0]);
+    assert.equal(trash.length, 0);
+    const readDeleted = await getFileMetadata(bob.cells[0], second_hash);
+    assert.isNull(readDeleted);
+  });
+});
+
+test("upload a file chunk by chunk", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    const session = await beginUpload(alice.cells[0], "/uploads", "story.txt");
+
+    // Chunks can be uploaded in any order
+    await uploadChunk(
+      alice.cells[0],
+      session,
+      1,
+      new TextEncoder().encode("upon a time")
+    );
+
+    // Finishing with a missing chunk (should fail)
+    await expect(finishUpload(alice.cells[0], session)).rejects.toThrow();
+
+    // Bob cannot upload chunks in Alice's session (should fail)
+    await expect(
+      uploadChunk(bob.cells[0], session, 0, new TextEncoder().encode("x"))
+    ).rejects.toThrow();
+
+    // Uploading the same index again replaces the chunk
+    await uploadChunk(
+      alice.cells[0],
+      session,
+      0,
+      new TextEncoder().encode("twice ")
+    );
+    await uploadChunk(
+      alice.cells[0],
+      session,
+      0,
+      new TextEncoder().encode("once ")
+    );
+
+    const record = await finishUpload(alice.cells[0], session);
+    const decoded = decodeOutputs([record])[0] as FileMetadata;
+    assert.equal(decoded.size, 16);
+    assert.deepEqual(decoded.chunks_sizes, [5, 11]);
+
+    await pause(1200);
+
+    const chunksRecords = await getFileChunks(
+      bob.cells[0],
+      record.signed_action.hashed.hash
+    );
+    const content = chunksRecords
+      .map((r) => new TextDecoder().decode(decodeOutputs([r])[0] as Uint8Array))
+      .join("");
+    assert.equal(content, "once upon a time");
+  });
+});
+
+test("read byte ranges of a file", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    const input = fiveMbFileInput("/", "large_file.txt");
+    input.content.set(new TextEncoder().encode("start"), 0);
+    input.content.set(new TextEncoder().encode("middle"), 3 * 1024 * 1024);
+    input.content.set(new TextEncoder().encode("end"), 5 * 1024 * 1024 - 3);
+    const records = await createFile(alice.cells[0], input);
+    const original_action_hash =
+      records.file_metadata.signed_action.hashed.hash;
+    const decoded = decodeOutputs([records.file_metadata])[0] as FileMetadata;
+    assert.isAbove(decoded.chunks_sizes.length, 1);
+
+    await pause(1200);
+
+    let bytes = await readFileRange(bob.cells[0], original_action_hash, 0, 5);
+    assert.equal(new TextDecoder().decode(bytes), "start");
+
+    bytes = await readFileRange(
+      bob.cells[0],
+      original_action_hash,
+      3 * 1024 * 1024,
+      6
+    );
+    assert.equal(new TextDecoder().decode(bytes), "middle");
+
+    // A range spanning two chunks
+    const boundary = decoded.chunks_sizes[0];
+    bytes = await readFileRange(
+      bob.cells[0],
+      original_action_hash,
+      boundary - 4,
+      8
+    );
+    assert.deepEqual(
+      Array.from(bytes),
+      Array.from(input.content.slice(boundary - 4, boundary + 4))
+    );
+
+    // A range past the end of the file is truncated
+    bytes = await readFileRange(
+      bob.cells[0],
+      original_action_hash,
+      5 * 1024 * 1024 - 3,
+      100
+    );
+    assert.equal(new TextDecoder().decode(bytes), "end");
+
+    bytes = await readFileRange(
+      bob.cells[0],
+      original_action_hash,
+      6 * 1024 * 1024,
+      100
+    );
+    assert.equal(bytes.length, 0);
+  });
+});
+
+test("get a single file chunk by its index", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    const input = fiveMbFileInput("/", "large_file.txt");
+    const records = await createFile(alice.cells[0], input);
+    const original_action_hash =
+      records.file_metadata.signed_action.hashed.hash;
+    const decoded = decodeOutputs([records.file_metadata])[0] as FileMetadata;
+    const chunks_count = decoded.chunks_hashes.length;
+
+    await pause(1200);
+
+    const output = await getFileChunkByIndex(
+      bob.cells[0],
+      original_action_hash,
+      chunks_count - 1
+    );
+    assert.equal(output.chunks_count, chunks_count);
+    const chunk = decodeOutputs([output.file_chunk])[0] as Uint8Array;
+    assert.equal(chunk.length, decoded.chunks_sizes[chunks_count - 1]);
+
+    // An index past the last chunk returns a dedicated error
+    try {
+      await getFileChunkByIndex(bob.cells[0], original_action_hash, chunks_count);
+      assert.fail("Expected an out of range error");
+    } catch (e) {
+      assert.include(
+        extractErrorMessage((e as Error).message),
+        "Chunk index out of range"
+      );
+    }
+  });
+});
+
+test("deleting a file keeps the chunks shared with other files", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    const first = await createFile(
+      alice.cells[0],
+      sampleFileInput("/", "first.txt", "shared content")
+    );
+    const second = await createFile(
+      bob.cells[0],
+      sampleFileInput("/", "second.txt", "shared content")
+    );
+    const first_hash = first.file_metadata.signed_action.hashed.hash;
+    const second_hash = second.file_metadata.signed_action.hashed.hash;
+    const copy = await copyFile(alice.cells[0], first_hash, "/copies");
+
+    await pause(1200);
+
+    // Delete the original file, the identical file and the copy still read
+    await deleteFile(alice.cells[0], first_hash);
+
+    await pause(1200);
+
+    let chunksRecords = await getFileChunks(bob.cells[0], second_hash);
+    assert.equal(
+      new TextDecoder().decode(decodeOutputs(chunksRecords)[0] as Uint8Array),
+      "shared content"
+    );
+    chunksRecords = await getFileChunks(
+      bob.cells[0],
+      copy.signed_action.hashed.hash
+    );
+    assert.equal(
+      new TextDecoder().decode(decodeOutputs(chunksRecords)[0] as Uint8Array),
+      "shared content"
+    );
+
+    // Updating a file keeps the chunks shared with another file
+    await updateFile(
+      bob.cells[0],
+      second_hash,
+      new TextEncoder().encode("new content")
+    );
+    await deleteFile(bob.cells[0], second_hash);
+
+    await pause(1200);
+
+    chunksRecords = await getFileChunks(
+      alice.cells[0],
+      copy.signed_action.hashed.hash
+    );
+    assert.equal(
+      new TextDecoder().decode(decodeOutputs(chunksRecords)[0] as Uint8Array),
+      "shared content"
+    );
+  });
+});
+
+test("collect orphan chunks", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    await createFile(alice.cells[0], sampleFileInput());
+    const session = await beginUpload(alice.cells[0], "/", "upload.txt");
+
+    // Replacing a chunk in an upload session leaves the first one orphaned
+    await uploadChunk(
+      alice.cells[0],
+      session,
+      0,
+      new TextEncoder().encode("orphan chunk")
+    );
+    await uploadChunk(
+      alice.cells[0],
+      session,
+      0,
+      new TextEncoder().encode("kept chunk")
+    );
+
+    await pause(1200);
+
+    let report = await collectGarbage(alice.cells[0]);
+    assert.equal(report.reclaimed_chunks, 1);
+    assert.equal(report.reclaimed_bytes, 12);
+
+    // The chunk still in the upload session was kept
+    const record = await finishUpload(alice.cells[0], session);
+    const decoded = decodeOutputs([record])[0] as FileMetadata;
+    assert.equal(decoded.size, 10);
+
+    await pause(1200);
+
+    report = await collectGarbage(alice.cells[0]);
+    assert.equal(report.reclaimed_chunks, 0);
+    const readOutput = await getFilesMetadataByPathRecursively(
+      bob.cells[0],
+      "/"
+    );
+    assert.equal(readOutput.length, 2);
+  });
+});
+
+test("store a content checksum and verify it", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    const records = await createFile(
+      alice.cells[0],
+      fiveMbFileInput("/", "large_file.txt")
+    );
+    const original_action_hash =
+      records.file_metadata.signed_action.hashed.hash;
+    const decoded = decodeOutputs([records.file_metadata])[0] as FileMetadata;
+    assert.equal(decoded.checksum.length, 64);
+
+    await pause(1200);
+
+    let verification = await verifyFile(bob.cells[0], original_action_hash);
+    assert.isTrue(verification.valid);
+    assert.equal(verification.computed_checksum, decoded.checksum);
+    assert.equal(
+      verification.computed_content_checksum,
+      decoded.content_checksum
+    );
+
+    // The checksum follows the content of the file
+    const updated = await updateFile(
+      alice.cells[0],
+      original_action_hash,
+      new TextEncoder().encode("new content")
+    );
+    const decodedUpdate = decodeOutputs([
+      updated.file_metadata,
+    ])[0] as FileMetadata;
+    assert.notEqual(decodedUpdate.checksum, decoded.checksum);
+
+    await pause(1200);
+
+    verification = await verifyFile(bob.cells[0], original_action_hash);
+    assert.isTrue(verification.valid);
+    assert.equal(verification.checksum, decodedUpdate.checksum);
+
+    // Compressed files keep the checksum of their uncompressed content
+    const compressed = await createFile(alice.cells[0], {
+      ...fiveMbFileInput("/", "compressed_file.txt"),
+      compression: "Deflate",
+    });
+    const decodedCompressed = decodeOutputs([
+      compressed.file_metadata,
+    ])[0] as FileMetadata;
+    assert.notEqual(decodedCompressed.checksum, decoded.checksum);
+    assert.equal(decodedCompressed.content_checksum, decoded.content_checksum);
+
+    await pause(1200);
+
+    verification = await verifyFile(
+      bob.cells[0],
+      compressed.file_metadata.signed_action.hashed.hash
+    );
+    assert.isTrue(verification.valid);
+    assert.equal(
+      verification.computed_content_checksum,
+      decoded.content_checksum
+    );
+  });
+});
+
+test("only the author of a file can update or delete it", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    const records = await createFile(
+      alice.cells[0],
+      sampleFileInput("/", "alice.txt")
+    );
+    const original_action_hash =
+      records.file_metadata.signed_action.hashed.hash;
+
+    await pause(1200);
+
+    await expect(
+      updateFile(
+        bob.cells[0],
+        original_action_hash,
+        new TextEncoder().encode("bob's content")
+      )
+    ).rejects.toThrow();
+    await expect(
+      moveFile(bob.cells[0], original_action_hash, "/bob")
+    ).rejects.toThrow();
+    await expect(
+      deleteFile(bob.cells[0], original_action_hash)
+    ).rejects.toThrow();
+
+    // The author can still update and delete the file
+    await updateFile(
+      alice.cells[0],
+      original_action_hash,
+      new TextEncoder().encode("alice's content")
+    );
+    await deleteFile(alice.cells[0], original_action_hash);
+
+    await pause(1200);
+
+    const readOutput = await getFileMetadata(
+      bob.cells[0],
+      original_action_hash
+    );
+    assert.isNull(readOutput);
+  });
+});
+
+test("account for the storage used by each agent", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    let usage = await getMyUsage(alice.cells[0]);
+    assert.equal(usage.files, 0);
+    assert.equal(usage.bytes, 0);
+    assert.isNull(usage.max_files);
+    assert.isNull(usage.max_bytes);
+
+    const records = await createFile(
+      alice.cells[0],
+      sampleFileInput("/", "a.txt")
+    );
+    const original_action_hash =
+      records.file_metadata.signed_action.hashed.hash;
+
+    // A copy shares the chunks of the original file, so it only adds a file
+    await copyFile(alice.cells[0], original_action_hash, "/", "b.txt");
+    usage = await getMyUsage(alice.cells[0]);
+    assert.equal(usage.files, 2);
+    assert.equal(usage.bytes, "hello world !".length);
+
+    // Previous versions are still stored
+    await updateFile(
+      alice.cells[0],
+      original_action_hash,
+      new TextEncoder().encode("new content")
+    );
+    usage = await getMyUsage(alice.cells[0]);
+    assert.equal(usage.files, 2);
+    assert.equal(
+      usage.bytes,
+      "hello world !".length + "new content".length
+    );
+
+    await deleteFile(alice.cells[0], original_action_hash);
+    usage = await getMyUsage(alice.cells[0]);
+    assert.equal(usage.files, 1);
+    assert.equal(usage.bytes, "hello world !".length);
+
+    usage = await getMyUsage(bob.cells[0]);
+    assert.equal(usage.files, 0);
+  });
+});
+
+test("use dots in file and folder names", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    await createDirectory(alice.cells[0], "/", ".config");
+    await createFile(alice.cells[0], sampleFileInput("/.config", "app.json"));
+    await createFile(
+      alice.cells[0],
+      sampleFileInput("/releases/v1.2", "notes.v1.2.txt")
+    );
+
+    // The current and parent folders cannot be used as names
+    await expect(
+      createDirectory(alice.cells[0], "/", "..")
+    ).rejects.toThrow();
+    await expect(
+      createFile(alice.cells[0], sampleFileInput("/a/../b", "test.txt"))
+    ).rejects.toThrow();
+
+    await pause(1200);
+
+    let content = await listDirectory(bob.cells[0], "/");
+    const paths = content.directories.map((d) => d.path).sort();
+    assert.deepEqual(paths, ["/.config", "/releases"]);
+
+    content = await listDirectory(bob.cells[0], "/releases");
+    assert.equal(content.directories[0].name, "v1.2");
+    assert.equal(content.directories[0].path, "/releases/v1.2");
+
+    content = await listDirectory(bob.cells[0], "/releases/v1.2");
+    assert.equal(content.files.length, 1);
+    const decoded = decodeOutputs(content.files)[0] as FileMetadata;
+    assert.equal(decoded.name, "notes.v1.2.txt");
+    assert.equal(decoded.path, "/releases/v1.2");
+
+    // A folder named "v1" is a different folder from "v1.2"
+    await expect(listDirectory(bob.cells[0], "/releases/v1")).rejects.toThrow();
+  });
+});
+
+test("normalize file and folder names to Unicode NFC", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    const nfc = "caf\u00e9";
+    const nfd = "cafe\u0301";
+
+    const records = await createFile(
+      alice.cells[0],
+      sampleFileInput(`/${nfd}`, `${nfd}.txt`)
+    );
+    const decoded = decodeOutputs([records.file_metadata])[0] as FileMetadata;
+    assert.equal(decoded.name, `${nfc}.txt`);
+    assert.equal(decoded.path, `/${nfc}`);
+
+    // The same name typed in another normalization form is the same file
+    await expect(
+      createFile(alice.cells[0], sampleFileInput(`/${nfc}`, `${nfc}.txt`))
+    ).rejects.toThrow();
+
+    await pause(1200);
+
+    const content = await listDirectory(bob.cells[0], `/${nfd}`);
+    assert.equal(content.files.length, 1);
+  });
+});
+
+test("resolve home folders of agents", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    const aliceHome = await getHomeDirectory(bob.cells[0], alice.agentPubKey);
+    assert.equal(
+      aliceHome,
+      await getHomeDirectory(alice.cells[0], alice.agentPubKey)
+    );
+    assert.match(aliceHome, /^\/[0-9a-f]{78}$/);
+
+    // Home folders are disabled by default, so "~" is an ordinary folder name
+    assert.equal(await resolvePath(alice.cells[0], "~/docs"), "/~/docs");
+
+    await createFile(alice.cells[0], sampleFileInput(`${aliceHome}/docs`));
+
+    await pause(1200);
+
+    const content = await listHomeDirectory(
+      bob.cells[0],
+      alice.agentPubKey,
+      "/docs"
+    );
+    assert.equal(content.files.length, 1);
+  });
+});
+
+test("store files in the home folders of agents", async () => {
+  await runScenarioWithTwoAgents(
+    async (scenario, alice, bob) => {
+      const aliceHome = await getHomeDirectory(
+        alice.cells[0],
+        alice.agentPubKey
+      );
+
+      // "~" refers to the home folder of the calling agent
+      assert.equal(await resolvePath(alice.cells[0], "~"), aliceHome);
+      assert.equal(
+        await resolvePath(alice.cells[0], "~/docs"),
+        `${aliceHome}/docs`
+      );
+      assert.equal(await resolvePath(alice.cells[0], "~docs"), "/~docs");
+
+      const records = await createFile(
+        alice.cells[0],
+        sampleFileInput("~/docs")
+      );
+      const decoded = decodeOutputs([records.file_metadata])[0] as FileMetadata;
+      assert.equal(decoded.path, `${aliceHome}/docs`);
+      await createDirectory(alice.cells[0], "~", "photos");
+
+      await pause(1200);
+
+      const content = await listHomeDirectory(
+        bob.cells[0],
+        alice.agentPubKey,
+        "/"
+      );
+      assert.sameMembers(
+        content.directories.map((directory) => directory.name),
+        ["docs", "photos"]
+      );
+
+      // Files and folders outside the home folder of their author are rejected by validation
+      await expect(
+        createFile(alice.cells[0], sampleFileInput("/docs"))
+      ).rejects.toThrow();
+      await expect(
+        createFile(
+          bob.cells[0],
+          sampleFileInput(`${aliceHome}/docs`, "bob.txt")
+        )
+      ).rejects.toThrow();
+      await expect(
+        createDirectory(alice.cells[0], "/", "photos")
+      ).rejects.toThrow();
+      await expect(
+        createDirectory(bob.cells[0], aliceHome, "bob")
+      ).rejects.toThrow();
+    },
+    { home_directories: true }
+  );
+});
+
+test("share files with other agents", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    const readRecords = await createFile(
+      alice.cells[0],
+      sampleFileInput("/", "read.txt")
+    );
+    const readHash = readRecords.file_metadata.signed_action.hashed.hash;
+    const writeRecords = await createFile(
+      alice.cells[0],
+      sampleFileInput("/", "write.txt")
+    );
+    const writeHash = writeRecords.file_metadata.signed_action.hashed.hash;
+
+    // Bob checks that Alice authored a file before storing its share
+    await pause(1200);
+
+    // Only the author of a file can share it
+    await expect(
+      shareFile(bob.cells[0], readHash, alice.agentPubKey, "Read")
+    ).rejects.toThrow();
+
+    await shareFile(alice.cells[0], readHash, bob.agentPubKey, "Read");
+    await shareFile(alice.cells[0], writeHash, bob.agentPubKey, "Write");
+
+    await pause(1200);
+
+    let sharedFiles = await listSharedWithMe(bob.cells[0]);
+    assert.equal(sharedFiles.length, 2);
+    assert.deepEqual(sharedFiles[0].owner, alice.agentPubKey);
+
+    // Bob can update the file shared for writing through Alice's cell, but not the one shared for reading
+    const updated = await updateSharedFile(
+      bob.cells[0],
+      alice.agentPubKey,
+      writeHash,
+      new TextEncoder().encode("updated by bob")
+    );
+    assert.deepEqual(
+      updated.file_metadata.signed_action.hashed.content.author,
+      alice.agentPubKey
+    );
+    await expect(
+      updateSharedFile(
+        bob.cells[0],
+        alice.agentPubKey,
+        readHash,
+        new TextEncoder().encode("updated by bob")
+      )
+    ).rejects.toThrow();
+
+    await unshareFile(alice.cells[0], writeHash, bob.agentPubKey);
+
+    await pause(1200);
+
+    sharedFiles = await listSharedWithMe(bob.cells[0]);
+    assert.equal(sharedFiles.length, 1);
+    await expect(
+      updateSharedFile(
+        bob.cells[0],
+        alice.agentPubKey,
+        writeHash,
+        new TextEncoder().encode("updated again")
+      )
+    ).rejects.toThrow();
+
+    // Deleting a file revokes its shares
+    await deleteFile(alice.cells[0], readHash);
+
+    await pause(1200);
+
+    sharedFiles = await listSharedWithMe(bob.cells[0]);
+    assert.equal(sharedFiles.length, 0);
+  });
+});
+
+test("read only the files shared by another agent", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    const sharedRecords = await createFile(
+      alice.cells[0],
+      sampleFileInput("/", "shared.txt", "shared content")
+    );
+    const sharedHash = sharedRecords.file_metadata.signed_action.hashed.hash;
+    const otherRecords = await createFile(
+      alice.cells[0],
+      sampleFileInput("/", "other.txt", "other content")
+    );
+    const otherHash = otherRecords.file_metadata.signed_action.hashed.hash;
+
+    await pause(1200);
+
+    await shareFile(alice.cells[0], sharedHash, bob.agentPubKey, "Read");
+
+    await pause(1200);
+
+    // Bob reads the shared file through Alice's cell
+    const metadata = await getSharedFileMetadata(
+      bob.cells[0],
+      alice.agentPubKey,
+      sharedHash
+    );
+    assert.equal(
+      (decodeOutputs([metadata])[0] as FileMetadata).name,
+      "shared.txt"
+    );
+    const chunks = await getSharedFileChunks(
+      bob.cells[0],
+      alice.agentPubKey,
+      sharedHash
+    );
+    assert.equal(
+      new TextDecoder().decode(decodeOutputs(chunks)[0] as Uint8Array),
+      "shared content"
+    );
+
+    // The grant of the shared file does not open the other files of Alice
+    await expect(
+      getSharedFileMetadata(bob.cells[0], alice.agentPubKey, otherHash)
+    ).rejects.toThrow();
+    await expect(
+      getSharedFileChunks(bob.cells[0], alice.agentPubKey, otherHash)
+    ).rejects.toThrow();
+  });
+});
+
+test("encrypt file content for selected agents", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    // Every agent publishes an encryption key when the zome is initialized
+    assert.ok(await getEncryptionKey(bob.cells[0], bob.agentPubKey));
+
+    await pause(1200);
+
+    const content = "secret content";
+    const sharedRecords = await createFile(alice.cells[0], {
+      ...sampleFileInput("/", "shared.txt", content),
+      encrypted_for: [bob.agentPubKey],
+    });
+    const sharedHash = sharedRecords.file_metadata.signed_action.hashed.hash;
+    const privateRecords = await createFile(alice.cells[0], {
+      ...sampleFileInput("/", "private.txt", content),
+      encrypted_for: [],
+    });
+    const privateHash = privateRecords.file_metadata.signed_action.hashed.hash;
+
+    const metadata = decodeOutputs([
+      sharedRecords.file_metadata,
+    ])[0] as FileMetadata;
+    assert.equal(metadata.size, content.length);
+    assert.equal(metadata.encryption?.wrapped_keys.length, 2);
+    // The metadata does not reveal the content of encrypted files
+    assert.isNull(metadata.content_checksum);
+    assert.deepEqual(metadata.content_sizes, []);
+
+    // The chunks stored on the DHT hold the encrypted content
+    const storedChunk = decodeOutputs(sharedRecords.file_chunks)[0] as Uint8Array;
+    assert.notEqual(new TextDecoder().decode(storedChunk), content);
+
+    await pause(1200);
+
+    const chunksRecords = await getFileChunks(bob.cells[0], sharedHash);
+    assert.equal(
+      new TextDecoder().decode(decodeOutputs(chunksRecords)[0] as Uint8Array),
+      content
+    );
+
+    await expect(getFileChunks(bob.cells[0], privateHash)).rejects.toThrow();
+    const aliceChunksRecords = await getFileChunks(alice.cells[0], privateHash);
+    assert.equal(
+      new TextDecoder().decode(
+        decodeOutputs(aliceChunksRecords)[0] as Uint8Array
+      ),
+      content
+    );
+  });
+});
+
+test("compress file chunks", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    const content = "a log line that repeats\n".repeat(1000);
+    const records = await createFile(alice.cells[0], {
+      ...sampleFileInput("/", "app.log", content),
+      compression: "Deflate",
+    });
+    const original_action_hash =
+      records.file_metadata.signed_action.hashed.hash;
+
+    const metadata = decodeOutputs([records.file_metadata])[0] as FileMetadata;
+    assert.equal(metadata.compression, "Deflate");
+    assert.equal(metadata.size, content.length);
+    assert.isBelow(metadata.stored_size, content.length / 10);
+    assert.equal(
+      metadata.content_sizes.reduce((sum, size) => sum + size, 0),
+      content.length
+    );
+
+    await pause(1200);
+
+    const chunksRecords = await getFileChunks(bob.cells[0], original_action_hash);
+    assert.equal(
+      decodeOutputs(chunksRecords)
+        .map((chunk) => new TextDecoder().decode(chunk as Uint8Array))
+        .join(""),
+      content
+    );
+
+    const range = await readFileRange(bob.cells[0], original_action_hash, 30, 10);
+    assert.equal(new TextDecoder().decode(range), content.slice(30, 40));
+
+    // Updates are compressed with the same codec
+    const updated = await updateFile(
+      alice.cells[0],
+      original_action_hash,
+      new TextEncoder().encode("short")
+    );
+    const updatedMetadata = decodeOutputs([
+      updated.file_metadata,
+    ])[0] as FileMetadata;
+    assert.equal(updatedMetadata.compression, "Deflate");
+    assert.equal(updatedMetadata.size, 5);
+  });
+});
+
+test("store private files on the source chain", async () => {
+  await runScenarioWithTwoAgents(async (scenario, alice, bob) => {
+    const content = "private notes";
+    const records = await createFile(alice.cells[0], {
+      ...sampleFileInput("/notes", "diary.txt", content),
+      private: true,
+    });
+    const original_action_hash =
+      records.file_metadata.signed_action.hashed.hash;
+    await createFile(alice.cells[0], sampleFileInput("/", "public.txt"));
+
+    // Private files do not count towards the storage quotas
+    assert.equal((await getMyUsage(alice.cells[0])).files, 1);
+
+    await expect(
+      createFile(alice.cells[0], sampleFileInput("/notes", "diary.txt"))
+    ).rejects.toThrow();
+
+    // The author lists and reads private files along with the public tree
+    const root = await listDirectory(alice.cells[0], "/");
+    assert.equal(root.files.length, 1);
+    assert.deepEqual(
+      root.directories.map((directory) => directory.name),
+      ["notes"]
+    );
+    const notes = await listDirectory(alice.cells[0], "/notes");
+    assert.equal(notes.files.length, 1);
+
+    const chunksRecords = await getFileChunks(
+      alice.cells[0],
+      original_action_hash
+    );
+    assert.equal(
+      new TextDecoder().decode(decodeOutputs(chunksRecords)[0] as Uint8Array),
+      content
+    );
+
+    const updated = await updateFile(
+      alice.cells[0],
+      original_action_hash,
+      new TextEncoder().encode("updated notes")
+    );
+    const range = await readFileRange(
+      alice.cells[0],
+      original_action_hash,
+      0,
+      7
+    );
+    assert.equal(new TextDecoder().decode(range), "updated");
+    const metadata = await getFileMetadata(
+      alice.cells[0],
+      original_action_hash
+    );
+    assert.deepEqual(
+      metadata?.signed_action.hashed.hash,
+      updated.file_metadata.signed_action.hashed.hash
+    );
+
+    await expect(
+      moveFile(alice.cells[0], original_action_hash, "/")
+    ).rejects.toThrow();
+
+    await pause(1200);
+
+    // Other agents never see private files
+    assert.notOk(await getFileMetadata(bob.cells[0], original_action_hash));
+    assert.equal((await listDirectory(bob.cells[0], "/")).directories.length, 0);
+    await expect(listDirectory(bob.cells[0], "/notes")).rejects.toThrow();
+
+    await deleteFile(alice.cells[0], original_action_hash);
+    assert.notOk(await getFileMetadata(alice.cells[0], original_action_hash));
+    assert.equal((await listDirectory(alice.cells[0], "/")).directories.length, 0);
+  });
+});
+
+test("enforce the storage quotas", async () => {
+  await runScenarioWithTwoAgents(
+    async (scenario, alice, bob) => {
+      await createFile(alice.cells[0], sampleFileInput("/", "a.txt", "aaaa"));
+      await createFile(alice.cells[0], sampleFileInput("/", "b.txt", "bbbb"));
+      const usage = await getMyUsage(alice.cells[0]);
+      assert.equal(usage.max_files, 2);
+      assert.equal(usage.max_bytes, 12);
+
+      // A third file exceeds the files quota
+      try {
+        await createFile(alice.cells[0], sampleFileInput("/", "c.txt", "c"));
+        assert.fail("Expected a files quota error");
+      } catch (e) {
+        assert.include(
+          extractErrorMessage((e as Error).message),
+          "the agent cannot store more than 2 files"
+        );
+      }
+
+      // A new version adding more bytes than left exceeds the bytes quota
+      const records = await createFile(
+        bob.cells[0],
+        sampleFileInput("/", "d.txt", "dddd")
+      );
+      try {
+        await updateFile(
+          bob.cells[0],
+          records.file_metadata.signed_action.hashed.hash,
+          new TextEncoder().encode("too many bytes")
+        );
+        assert.fail("Expected a bytes quota error");
+      } catch (e) {
+        assert.include(
+          extractErrorMessage((e as Error).message),
+          "the agent cannot store more than 12 bytes"
+        );
+      }
+
+      // The chunks of an unfinished upload count towards the bytes quota
+      const session = await beginUpload(bob.cells[0], "/", "upload.txt");
+      await uploadChunk(
+        bob.cells[0],
+        session,
+        0,
+        new TextEncoder().encode("eeee")
+      );
+      assert.equal((await getMyUsage(bob.cells[0])).bytes, 8);
+      try {
+        await uploadChunk(
+          bob.cells[0],
+          session,
+          1,
+          new TextEncoder().encode("ffffffff")
+        );
+        assert.fail("Expected a bytes quota error");
+      } catch (e) {
+        assert.include(
+          extractErrorMessage((e as Error).message),
+          "the agent cannot store more than 12 bytes"
+        );
+      }
+
+      // Cancelling the upload releases its chunks
+      await cancelUpload(bob.cells[0], session);
+      assert.equal((await getMyUsage(bob.cells[0])).bytes, 4);
+    },
+    { max_files_per_agent: 2, max_bytes_per_agent: 12 }
+  );
+});
+
+test("keep the case of folder names in case-insensitive mode", async () => {
+  await runScenarioWithTwoAgents(
+    async (scenario, alice, bob) => {
+      await createDirectory(alice.cells[0], "/", "Documents");
+      await createDirectory(alice.cells[0], "/Documents", "Photos");
+
+      await pause(1200);
+
+      let content = await listDirectory(bob.cells[0], "/");
+      assert.equal(content.directories.length, 1);
+      assert.equal(content.directories[0].name, "Documents");
+      assert.equal(content.directories[0].path, "/Documents");
+
+      // Folders are looked up by their lowercase name
+      content = await listDirectory(bob.cells[0], "/documents");
+      assert.equal(content.directories.length, 1);
+      assert.equal(content.directories[0].name, "Photos");
+      assert.equal(content.directories[0].path, "/Documents/Photos");
+
+      // A folder differing only by its case already exists
+      await expect(
+        createDirectory(alice.cells[0], "/", "DOCUMENTS")
+      ).rejects.toThrow();
+    },
+    { case_insensitive: true }
+  );
+});
+
 function extractErrorMessage(input: string): string | null {
   const regex = /Guest\("([^"]+)"\)/;
   const match = input.match(regex);